@@ -1,20 +1,69 @@
 use eframe::egui;
+use std::collections::HashMap;
 
-use crate::file_system::file_tree;
-use crate::ui::{settings::Settings, window};
-use crate::views;
+use crate::config::{Config, ConfigFile};
+use crate::file_system::{
+    error::FsResult,
+    jobs::{self, FinishedJob, JobKind, JobOrigin, JobOutcome, JobQueue, Resolution},
+    journal::{Journal, JournalEntry, Operation, Step},
+    loader::DirLoader,
+    operations,
+    operations::{Clipboard, ClipboardMode, FileAction},
+    trash::{self, TrashEntry, TrashLister},
+    watcher::DirWatcher,
+};
+use crate::tab::{ClosedTab, Tab};
+use crate::ui::{
+    self,
+    color_schemes::ColorSchemes,
+    conflicts::{ConflictDialog, ConflictOutcome},
+    notifications::Notifications,
+    places::{Places, PlacesRequest},
+    settings::{Settings, View},
+    system_theme::SystemTheme,
+    tabs::TabCommand,
+    window,
+};
+use crate::views::{
+    self,
+    columns::{self, Column},
+};
+
+// Closed tabs remembered for reopening
+const MAX_CLOSED_TABS: usize = 20;
 
 pub struct MyApp {
-    current_root: std::path::PathBuf,
-    history: Vec<std::path::PathBuf>,
-    history_index: usize,
-    file_tree: file_tree::FileNode,
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    closed_tabs: Vec<ClosedTab>,
+    next_tab_id: u64,
+    // The right pane of dual-pane mode, the active tab is shown on the left
+    second_pane: Option<Tab>,
+    right_pane_active: bool,
+    // Shared by all tabs and panes
+    loader: DirLoader,
+    watcher: DirWatcher,
+    jobs: JobQueue,
+    conflict_dialog: Option<ConflictDialog>,
+    notifications: Notifications,
+    journal: Journal,
+    places: Places,
+    clipboard: Option<Clipboard>,
+    trash_entries: Vec<TrashEntry>,
+    trash_lister: TrashLister,
     settings: Settings,
     show_settings: bool,
+    system_theme: SystemTheme,
+    color_schemes: ColorSchemes,
+    config_file: ConfigFile,
 }
 
 impl MyApp {
-    pub fn new(cc: &eframe::CreationContext, initial_path: std::path::PathBuf) -> Self {
+    pub fn new(
+        cc: &eframe::CreationContext,
+        initial_path: std::path::PathBuf,
+        config_path: std::path::PathBuf,
+    ) -> Self {
         let mut fonts = egui::FontDefinitions::default();
 
         egui_phosphor::add_to_fonts(&mut fonts, egui_phosphor::Variant::Regular);
@@ -22,50 +71,599 @@ impl MyApp {
 
         cc.egui_ctx.set_fonts(fonts);
 
-        let mut tree = file_tree::FileNode::new(&initial_path);
-        tree.ensure_children_loaded();
+        let mut notifications = Notifications::default();
+        // Theme files sit next to the config
+        let themes_dir = config_path.with_file_name("themes");
+        let mut config_file = ConfigFile::new(&cc.egui_ctx, config_path);
+        let config = config_file.load().unwrap_or_else(|error| {
+            notifications.error(error, None);
+            Config::default()
+        });
+        if let Some(notice) = config_file.take_notice() {
+            notifications.error(notice, None);
+        }
+        let settings = config.settings;
+        let mut loader = DirLoader::new(&cc.egui_ctx);
+        loader.set_count_items(columns::is_shown(&settings.list_columns, Column::ItemCount));
+        let tab = Tab::new(0, initial_path, settings.view.clone(), &mut loader);
 
         Self {
-            current_root: initial_path.clone(),
-            history: vec![initial_path.clone()],
-            history_index: 0,
-            file_tree: tree,
-            settings: Settings::default(),
+            tabs: vec![tab],
+            active_tab: 0,
+            closed_tabs: Vec::new(),
+            next_tab_id: 1,
+            second_pane: None,
+            right_pane_active: false,
+            loader,
+            watcher: DirWatcher::new(&cc.egui_ctx),
+            jobs: JobQueue::new(&cc.egui_ctx),
+            conflict_dialog: None,
+            notifications,
+            journal: Journal::load(),
+            places: Places::new(config.favorites),
+            clipboard: None,
+            trash_entries: Vec::new(),
+            trash_lister: TrashLister::new(&cc.egui_ctx),
+            settings,
             show_settings: false,
+            system_theme: SystemTheme::new(&cc.egui_ctx),
+            color_schemes: ColorSchemes::new(&cc.egui_ctx, themes_dir),
+            config_file,
         }
     }
 
-    fn go_to_directory(&mut self, path: &std::path::Path) {
-        if path.is_dir() && path != self.current_root.as_path() {
-            if self.history_index < self.history.len().saturating_sub(1) {
-                self.history.truncate(self.history_index + 1);
+    // The tab of the active pane
+    fn tab(&self) -> &Tab {
+        match &self.second_pane {
+            Some(pane) if self.right_pane_active => pane,
+            _ => &self.tabs[self.active_tab],
+        }
+    }
+
+    fn tab_mut(&mut self) -> &mut Tab {
+        match &mut self.second_pane {
+            Some(pane) if self.right_pane_active => pane,
+            _ => &mut self.tabs[self.active_tab],
+        }
+    }
+
+    // Run a navigation of the active pane's tab, which may list a folder
+    fn navigate(&mut self, go: impl FnOnce(&mut Tab, &mut DirLoader)) {
+        let tab = match &mut self.second_pane {
+            Some(pane) if self.right_pane_active => pane,
+            _ => &mut self.tabs[self.active_tab],
+        };
+        go(tab, &mut self.loader);
+    }
+
+    // Apply what the loader and watcher found since the last frame. Background tabs keep
+    // loading and following changes too.
+    fn poll_tabs(&mut self) {
+        let collation = self.settings.collation();
+        for tab in self.tabs.iter_mut().chain(&mut self.second_pane) {
+            tab.set_collation(collation);
+        }
+
+        // Fill the Items column once it's shown
+        let count_items = columns::is_shown(&self.settings.list_columns, Column::ItemCount);
+        if self.loader.set_count_items(count_items) {
+            for tab in self.tabs.iter_mut().chain(&mut self.second_pane) {
+                let root = tab.current_root.clone();
+                tab.reload(&root, &mut self.loader);
             }
+        }
+
+        let trees = self
+            .tabs
+            .iter_mut()
+            .chain(&mut self.second_pane)
+            .map(|tab| (tab.id, &mut tab.file_tree));
+        for error in self.loader.poll(trees, collation) {
+            let retry = FileAction::Reload(error.path.clone());
+            self.notifications.error(error, Some(retry));
+        }
+
+        let trees = self
+            .tabs
+            .iter_mut()
+            .chain(&mut self.second_pane)
+            .map(|tab| &mut tab.file_tree);
+        for path in self.watcher.poll(trees, collation) {
+            for tab in self.tabs.iter_mut().chain(&mut self.second_pane) {
+                tab.reload(&path, &mut self.loader);
+            }
+        }
 
-            self.history.push(path.to_path_buf());
-            self.history_index += 1;
+        let trees = self
+            .tabs
+            .iter()
+            .chain(&self.second_pane)
+            .map(|tab| &tab.file_tree);
+        self.watcher.sync(trees);
+    }
 
-            self.current_root = path.to_path_buf();
+    // Open or drop the right pane to match the settings. It starts where the left one is.
+    fn sync_panes(&mut self) {
+        if self.settings.dual_pane == self.second_pane.is_some() {
+            return;
+        }
 
-            let mut node = file_tree::FileNode::new(path);
-            node.ensure_children_loaded();
-            self.file_tree = node;
+        if self.settings.dual_pane {
+            let left = &self.tabs[self.active_tab];
+            let pane = Tab::new(
+                self.next_tab_id,
+                left.current_root.clone(),
+                left.view.clone(),
+                &mut self.loader,
+            );
+            self.next_tab_id += 1;
+            self.second_pane = Some(pane);
+        } else {
+            if let Some(pane) = self.second_pane.take() {
+                self.loader.cancel_tab(pane.id);
+            }
+            self.right_pane_active = false;
         }
     }
 
-    fn go_back(&mut self) {
-        if self.history_index > 0 {
-            self.history_index -= 1;
-            let prev_path = &self.history[self.history_index];
-            self.current_root = prev_path.clone();
-            let mut node = file_tree::FileNode::new(prev_path);
-            node.ensure_children_loaded();
-            self.file_tree = node;
+    // F5 copies and F6 moves what's selected in the active pane to the folder of the other one
+    fn transfer_to_other_pane(&mut self, copy: bool) {
+        let Some(pane) = &self.second_pane else {
+            return;
+        };
+        let left = &self.tabs[self.active_tab];
+        let (from, to) = if self.right_pane_active {
+            (pane, left)
+        } else {
+            (left, pane)
+        };
+        if from.show_trash || to.show_trash {
+            return;
+        }
+
+        let paths = from.selected_paths();
+        if paths.is_empty() {
+            return;
+        }
+        let to_dir = to.current_root.clone();
+        if copy {
+            self.run_action(FileAction::Copy { paths, to_dir });
+        } else {
+            self.run_action(FileAction::Move { paths, to_dir });
         }
     }
 
-    fn can_go_back(&self) -> bool {
-        self.history_index > 0
+    // Open a folder in a new tab next to the active one, switching to it when asked
+    fn open_tab(&mut self, path: std::path::PathBuf, activate: bool) {
+        let tab = Tab::new(
+            self.next_tab_id,
+            path,
+            self.settings.view.clone(),
+            &mut self.loader,
+        );
+        self.next_tab_id += 1;
+        self.tabs.insert(self.active_tab + 1, tab);
+        if activate {
+            self.active_tab += 1;
+        }
+    }
+
+    // The last tab stays open
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.len() < 2 || index >= self.tabs.len() {
+            return;
+        }
+
+        let tab = self.tabs.remove(index);
+        self.closed_tabs.push(tab.close(&mut self.loader));
+        if self.closed_tabs.len() > MAX_CLOSED_TABS {
+            self.closed_tabs.remove(0);
+        }
+        if self.active_tab > index || self.active_tab == self.tabs.len() {
+            self.active_tab -= 1;
+        }
+    }
+
+    fn reopen_closed_tab(&mut self) {
+        if let Some(closed) = self.closed_tabs.pop() {
+            let tab = Tab::reopen(self.next_tab_id, closed, &mut self.loader);
+            self.next_tab_id += 1;
+            self.tabs.insert(self.active_tab + 1, tab);
+            self.active_tab += 1;
+        }
+    }
+
+    fn run_tab_command(&mut self, command: TabCommand) {
+        match command {
+            TabCommand::New => self.open_tab(self.tab().current_root.clone(), true),
+            TabCommand::Close(index) => self.close_tab(index),
+            TabCommand::CloseOthers(index) => {
+                let keep = self.tabs[index].id;
+                while let Some(index) = self.tabs.iter().rposition(|tab| tab.id != keep) {
+                    self.close_tab(index);
+                }
+            }
+            TabCommand::Reopen => self.reopen_closed_tab(),
+        }
+    }
+
+    fn refresh(&mut self) {
+        if self.tab().show_trash {
+            self.trash_lister.rescan();
+        } else {
+            self.run_action(FileAction::Reload(self.tab().current_root.clone()));
+        }
+    }
+
+    // Patch a changed path in every tab showing it
+    fn refresh_path(&mut self, path: &std::path::Path) {
+        for tab in self.tabs.iter_mut().chain(&mut self.second_pane) {
+            tab.file_tree.refresh_path(path, self.settings.collation());
+        }
     }
+
+    // Carry out a filesystem change and patch the tree, failures end up as toasts.
+    // Returns whether it succeeded.
+    fn run_action(&mut self, action: FileAction) -> bool {
+        let result = match &action {
+            FileAction::Reload(path) => {
+                for tab in self.tabs.iter_mut().chain(&mut self.second_pane) {
+                    tab.reload(path, &mut self.loader);
+                }
+                Ok(())
+            }
+            FileAction::Delete(paths) => {
+                self.jobs
+                    .submit(JobKind::Delete, paths.clone(), HashMap::new());
+                Ok(())
+            }
+            FileAction::Move { paths, to_dir } => {
+                let kind = JobKind::Move {
+                    to_dir: to_dir.clone(),
+                };
+                self.start_transfer(kind, paths.clone());
+                Ok(())
+            }
+            FileAction::Copy { paths, to_dir } => {
+                let kind = JobKind::Copy {
+                    to_dir: to_dir.clone(),
+                };
+                self.start_transfer(kind, paths.clone());
+                Ok(())
+            }
+            FileAction::SetClipboard(clipboard) => {
+                self.clipboard = Some(clipboard.clone());
+                Ok(())
+            }
+            FileAction::StartRename(path) => {
+                self.tab_mut().view_state.start_rename(path);
+                Ok(())
+            }
+            FileAction::Rename { from, new_name } => {
+                operations::rename_path(from, new_name).map(|target_path| {
+                    for tab in self.tabs.iter_mut().chain(&mut self.second_pane) {
+                        tab.file_tree.rename_entry(from, &target_path);
+                        tab.view_state.selection.rename(from, &target_path);
+                    }
+                    self.record(Operation::Rename {
+                        from: from.clone(),
+                        to: target_path,
+                    });
+                })
+            }
+            FileAction::NewFolder(dir) | FileAction::NewFile(dir) => {
+                let is_dir = matches!(action, FileAction::NewFolder(_));
+                operations::create_entry(dir, is_dir).map(|path| {
+                    self.refresh_path(&path);
+                    self.tab_mut().view_state.start_rename(&path);
+                    self.record(Operation::Create { path, is_dir });
+                })
+            }
+            FileAction::Trash(paths) => {
+                self.jobs
+                    .submit(JobKind::Trash, paths.clone(), HashMap::new());
+                Ok(())
+            }
+            FileAction::RestoreFromTrash(entry) => trash::restore(entry).map(|restored| {
+                self.refresh_path(&restored);
+                self.trash_lister.rescan();
+            }),
+            FileAction::DeleteFromTrash(entry) => {
+                trash::delete_entry(entry).map(|()| self.trash_lister.rescan())
+            }
+            FileAction::EmptyTrash => {
+                let result = trash::empty();
+                self.trash_lister.rescan();
+                result
+            }
+            FileAction::Paste { to_dir } => {
+                let Some(clipboard) = self.clipboard.clone() else {
+                    return false;
+                };
+
+                let to_dir = to_dir.clone();
+                let kind = match clipboard.mode {
+                    ClipboardMode::Copy => JobKind::Copy { to_dir },
+                    ClipboardMode::Cut => JobKind::Move { to_dir },
+                };
+                self.start_transfer(kind, clipboard.paths);
+                Ok(())
+            }
+            FileAction::Undo => {
+                let step = self.journal.undo();
+                self.run_step(step, JobOrigin::Undo)
+            }
+            FileAction::AddToFavorites(path) => {
+                self.places.add(path.clone());
+                Ok(())
+            }
+            FileAction::Redo => {
+                let step = self.journal.redo();
+                self.run_step(step, JobOrigin::Redo)
+            }
+        };
+
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                // A failed undo or redo step stays on its stack, so retrying picks it up again
+                self.notifications.error(e, Some(action));
+                false
+            }
+        }
+    }
+
+    // Add a finished operation to the undo journal and offer to undo it
+    fn record(&mut self, operation: Operation) {
+        if let Some(entry) = self.journal.record(operation) {
+            let title = entry.describe();
+            self.notifications
+                .info(title, Some(("Undo", FileAction::Undo)));
+        }
+        self.save_journal();
+    }
+
+    // Keep the journal on disk in step with every change to it
+    fn save_journal(&mut self) {
+        if let Err(error) = self.journal.save() {
+            self.notifications.error(error, None);
+        }
+    }
+
+    // Queue a copy or move, asking first what to do about names already taken in the target folder
+    fn start_transfer(&mut self, kind: JobKind, sources: Vec<std::path::PathBuf>) {
+        let (sources, conflicts) = jobs::plan_transfer(&kind, sources);
+        if conflicts.is_empty() {
+            self.submit_transfer(kind, sources, HashMap::new());
+        } else {
+            self.conflict_dialog = Some(ConflictDialog::new(kind, sources, conflicts));
+        }
+    }
+
+    fn submit_transfer(
+        &mut self,
+        kind: JobKind,
+        sources: Vec<std::path::PathBuf>,
+        resolutions: HashMap<std::path::PathBuf, Resolution>,
+    ) {
+        // Cut items can only be pasted once, failures can still be retried from their toasts
+        if matches!(kind, JobKind::Move { .. })
+            && self.clipboard.as_ref().is_some_and(|clipboard| {
+                clipboard.mode == ClipboardMode::Cut
+                    && clipboard.paths.iter().any(|path| sources.contains(path))
+            })
+        {
+            self.clipboard = None;
+        }
+        self.jobs.submit(kind, sources, resolutions);
+    }
+
+    // Patch the tree with what a background job did. Everything it did is undone in one step.
+    // Carry out an undo or redo step, on the spot or as a job that finish_job settles
+    fn run_step(&mut self, step: Option<Step>, origin: JobOrigin) -> FsResult<()> {
+        match step {
+            Some(Step::Done(result)) => {
+                self.save_journal();
+                result.map(|entry| self.step_done(&entry, origin))
+            }
+            Some(Step::Transfer { kind, pairs }) => {
+                self.jobs.submit_step(kind, origin, pairs);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Show what an undo or redo step changed and offer to reverse it
+    fn step_done(&mut self, entry: &JournalEntry, origin: JobOrigin) {
+        self.refresh_journal_paths(entry);
+        if origin == JobOrigin::Undo {
+            let title = format!("Undone: {}", entry.describe());
+            self.notifications
+                .info(title, Some(("Redo", FileAction::Redo)));
+        } else {
+            self.notifications
+                .info(entry.describe(), Some(("Undo", FileAction::Undo)));
+        }
+    }
+
+    fn finish_job(&mut self, job: FinishedJob) {
+        if job.origin != JobOrigin::User {
+            self.finish_step(job);
+            return;
+        }
+        if job
+            .outcomes
+            .iter()
+            .any(|outcome| matches!(outcome, JobOutcome::Trashed(_)))
+        {
+            self.trash_lister.rescan();
+        }
+
+        self.journal.begin_batch();
+        for outcome in job.outcomes {
+            match outcome {
+                JobOutcome::Copied { from, to } => {
+                    self.refresh_path(&to);
+                    self.journal.record(Operation::Copy { from, to });
+                }
+                JobOutcome::Moved { from, to } => {
+                    self.refresh_path(&from);
+                    self.refresh_path(&to);
+                    self.journal.record(Operation::Move { from, to });
+                }
+                JobOutcome::Deleted(path) => self.refresh_path(&path),
+                JobOutcome::Trashed(entry) => {
+                    self.refresh_path(&entry.original_path);
+                    self.journal.record(Operation::Trash { entry });
+                }
+            }
+        }
+        if let Some(entry) = self.journal.end_batch() {
+            let mut title = entry.describe();
+            if job.cancelled {
+                title = format!("{} (cancelled)", title);
+            }
+            self.notifications
+                .info(title, Some(("Undo", FileAction::Undo)));
+        }
+        self.save_journal();
+
+        for (source, error) in job.errors {
+            let retry = job.kind.retry_action(&source);
+            self.notifications.error(error, Some(retry));
+        }
+    }
+
+    // Move the part of an undo or redo step its job got through to the other stack
+    fn finish_step(&mut self, job: FinishedJob) {
+        let done: Vec<(std::path::PathBuf, std::path::PathBuf)> = job
+            .outcomes
+            .into_iter()
+            .filter_map(|outcome| match outcome {
+                JobOutcome::Copied { from, to } | JobOutcome::Moved { from, to } => {
+                    Some((from, to))
+                }
+                _ => None,
+            })
+            .collect();
+        if let Some(entry) = self.journal.finish_transfer(&done) {
+            self.step_done(&entry, job.origin);
+        }
+        self.save_journal();
+
+        // What failed stays on its stack, so retrying takes the same step again
+        let retry = if job.origin == JobOrigin::Undo {
+            FileAction::Undo
+        } else {
+            FileAction::Redo
+        };
+        for (_, error) in job.errors {
+            self.notifications.error(error, Some(retry.clone()));
+        }
+    }
+
+    fn refresh_journal_paths(&mut self, entry: &JournalEntry) {
+        for path in entry.touched_paths() {
+            self.refresh_path(&path);
+        }
+        if entry.touches_trash() {
+            self.trash_lister.rescan();
+        }
+    }
+
+    fn open_trash(&mut self) {
+        self.tab_mut().show_trash = true;
+        self.trash_lister.rescan();
+    }
+}
+
+fn nav_button(ui: &mut egui::Ui, icon: &str, enabled: bool, hover: &str) -> egui::Response {
+    ui.add_enabled(
+        enabled,
+        egui::Button::new(egui::RichText::new(icon).size(20.0)),
+    )
+    .on_hover_text(hover)
+}
+
+// Dropdown of the whole history, opened by right-clicking a back or forward button or holding
+// it down past a click. Returns the entry picked.
+fn history_menu(
+    ui: &egui::Ui,
+    button: &egui::Response,
+    history: &[std::path::PathBuf],
+    current: usize,
+) -> Option<usize> {
+    let held = button.is_pointer_button_down_on();
+    if held {
+        // Nothing else moves while the button is held, keep checking for the long press
+        ui.ctx().request_repaint();
+    }
+    let max_click = ui.ctx().options(|o| o.input_options.max_click_duration);
+    let long_press = held
+        && ui.input(|i| {
+            i.pointer
+                .press_start_time()
+                .is_some_and(|start| i.time - start > max_click)
+        });
+
+    let mut jump = None;
+    egui::Popup::from_response(button)
+        .kind(egui::PopupKind::Menu)
+        .layout(egui::Layout::top_down_justified(egui::Align::Min))
+        .open_memory(
+            (button.secondary_clicked() || long_press).then_some(egui::SetOpenCommand::Bool(true)),
+        )
+        .show(|ui| {
+            // Newest first, like the order the forward and back buttons walk in
+            for (index, path) in history.iter().enumerate().rev() {
+                if ui
+                    .selectable_label(index == current, path.display().to_string())
+                    .clicked()
+                {
+                    jump = Some(index);
+                    ui.close();
+                }
+            }
+        });
+    jump
+}
+
+// Draw a tab's folder, or the trash when it's open there
+fn show_tab_contents(
+    ui: &mut egui::Ui,
+    tab: &mut Tab,
+    settings: &Settings,
+    clipboard: Option<&Clipboard>,
+    trash_entries: &[TrashEntry],
+) -> views::ViewResponse {
+    if tab.show_trash {
+        return views::ViewResponse {
+            actions: views::trash::render_trash_view(ui, trash_entries),
+            ..Default::default()
+        };
+    }
+
+    let settings = Settings {
+        view: tab.view.clone(),
+        ..settings.clone()
+    };
+    // Scroll positions and such are kept per tab
+    ui.push_id(tab.id, |ui| {
+        views::common::scroll_area()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                views::render_file_node(
+                    ui,
+                    &tab.file_tree,
+                    &mut tab.view_state,
+                    &settings,
+                    clipboard,
+                )
+            })
+            .inner
+    })
+    .inner
 }
 
 impl eframe::App for MyApp {
@@ -74,75 +672,393 @@ impl eframe::App for MyApp {
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.settings.apply_theme(ctx);
+        // The config file was edited outside, e.g. by hand
+        match self.config_file.poll() {
+            Some(Ok(config)) => {
+                self.settings = config.settings;
+                self.places.set_favorites(config.favorites);
+            }
+            Some(Err(error)) => self.notifications.error(error, None),
+            None => {}
+        }
+        if let Some(notice) = self.config_file.take_notice() {
+            self.notifications.error(notice, None);
+        }
+
+        self.color_schemes.poll();
+        let scheme = self
+            .color_schemes
+            .current(self.settings.color_scheme.as_deref());
+        self.settings
+            .apply_theme(ctx, self.system_theme.prefers_dark(), scheme);
+        self.sync_panes();
+        self.poll_tabs();
+        for job in self.jobs.poll() {
+            self.finish_job(job);
+        }
+        if let Some(entries) = self.trash_lister.poll() {
+            self.trash_entries = entries;
+        }
+
+        let new_folder_shortcut = egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::N,
+        );
+        if !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| i.consume_shortcut(&new_folder_shortcut))
+        {
+            self.run_action(FileAction::NewFolder(self.tab().current_root.clone()));
+        }
+
+        if !ctx.wants_keyboard_input() {
+            let new_tab_shortcut =
+                egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::T);
+            let reopen_tab_shortcut = egui::KeyboardShortcut::new(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::T,
+            );
+            let close_tab_shortcut =
+                egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::W);
+
+            // Checked first, the plain Ctrl+T shortcut also matches with Shift held
+            let command = ctx.input_mut(|i| {
+                if i.consume_shortcut(&reopen_tab_shortcut) {
+                    Some(TabCommand::Reopen)
+                } else if i.consume_shortcut(&new_tab_shortcut) {
+                    Some(TabCommand::New)
+                } else if i.consume_shortcut(&close_tab_shortcut) {
+                    Some(TabCommand::Close(self.active_tab))
+                } else {
+                    None
+                }
+            });
+            if let Some(command) = command {
+                self.run_tab_command(command);
+            }
+        }
+
+        // Text fields handle their own undo
+        if !ctx.wants_keyboard_input() {
+            let redo_shortcut = egui::KeyboardShortcut::new(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::Z,
+            );
+            let redo_alt_shortcut =
+                egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
+            let undo_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+
+            // Checked first, the plain Ctrl+Z shortcut also matches with Shift held
+            if ctx.input_mut(|i| {
+                i.consume_shortcut(&redo_shortcut) || i.consume_shortcut(&redo_alt_shortcut)
+            }) {
+                self.run_action(FileAction::Redo);
+            } else if ctx.input_mut(|i| i.consume_shortcut(&undo_shortcut)) {
+                self.run_action(FileAction::Undo);
+            }
+        }
+
+        let mut switched_pane = false;
+        if self.second_pane.is_some() && !ctx.wants_keyboard_input() {
+            let (copy, move_, switch) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::F5),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::F6),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Tab),
+                )
+            });
+            if copy || move_ {
+                self.transfer_to_other_pane(copy);
+            }
+            if switch {
+                self.right_pane_active = !self.right_pane_active;
+                switched_pane = true;
+            }
+        }
+
+        // Alt+Left/Right and the side buttons of the mouse walk the history
+        if !ctx.wants_keyboard_input() {
+            let back_shortcut =
+                egui::KeyboardShortcut::new(egui::Modifiers::ALT, egui::Key::ArrowLeft);
+            let forward_shortcut =
+                egui::KeyboardShortcut::new(egui::Modifiers::ALT, egui::Key::ArrowRight);
+            let refresh_shortcut =
+                egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::R);
+
+            let (back, forward, refresh) = ctx.input_mut(|i| {
+                (
+                    i.consume_shortcut(&back_shortcut)
+                        || i.pointer.button_pressed(egui::PointerButton::Extra1),
+                    i.consume_shortcut(&forward_shortcut)
+                        || i.pointer.button_pressed(egui::PointerButton::Extra2),
+                    i.consume_shortcut(&refresh_shortcut),
+                )
+            });
+            if back {
+                self.navigate(Tab::go_back);
+            } else if forward {
+                self.navigate(Tab::go_forward);
+            }
+            if refresh {
+                self.refresh();
+            }
+        }
 
         if self.show_settings {
-            self.settings.ui(ctx, &mut self.show_settings);
+            self.settings
+                .ui(ctx, &mut self.show_settings, &mut self.color_schemes);
+        }
+
+        if let Some(dialog) = &mut self.conflict_dialog
+            && let Some(outcome) = dialog.show(ctx)
+        {
+            self.conflict_dialog = None;
+            if let ConflictOutcome::Resolved {
+                kind,
+                sources,
+                resolutions,
+            } = outcome
+            {
+                self.submit_transfer(kind, sources, resolutions);
+            }
         }
 
         let mut show_settings_toggle = false;
 
         window::custom_window_frame(ctx, "Rustplorer", &mut show_settings_toggle, |ui| {
+            egui::TopBottomPanel::top("tab_strip").show_inside(ui, |ui| {
+                let active_tab = self.active_tab;
+                let command = ui::tabs::show_tab_strip(
+                    ui,
+                    &mut self.tabs,
+                    &mut self.active_tab,
+                    !self.closed_tabs.is_empty(),
+                );
+                // Tabs live in the left pane
+                if self.active_tab != active_tab {
+                    self.right_pane_active = false;
+                }
+                if let Some(command) = command {
+                    self.run_tab_command(command);
+                }
+            });
+
             egui::SidePanel::left("Favorites")
                 .resizable(true)
                 .default_width(150.0)
                 .width_range(90.0..=200.0)
                 .show_inside(ui, |ui| {
-                    ui.heading("Favorites");
-                    ui.separator();
-                    ui.label("TODO!");
+                    let (current, show_trash) =
+                        (self.tab().current_root.clone(), self.tab().show_trash);
+                    match self.places.show(ui, &current, show_trash) {
+                        Some(PlacesRequest::Open(path)) => {
+                            self.tab_mut().show_trash = false;
+                            self.navigate(|tab, loader| tab.go_to_directory(&path, loader));
+                        }
+                        Some(PlacesRequest::OpenInNewTab(path)) => {
+                            self.open_tab(path, false);
+                        }
+                        Some(PlacesRequest::OpenTrash) => self.open_trash(),
+                        None => {}
+                    }
                 });
 
             egui::TopBottomPanel::top("nav_bar").show_inside(ui, |ui| {
-                ui.horizontal(|ui| {
-                    let back_enabled = self.can_go_back();
-                    let back_button = ui
-                        .add_enabled(
-                            back_enabled,
-                            egui::Button::new(
-                                egui::RichText::new(format!(
-                                    "{}",
-                                    egui_phosphor::regular::ARROW_LEFT
-                                ))
-                                .size(20.0),
-                            ),
-                        )
-                        .on_hover_text("Go back");
+                let show_hidden = self.settings.show_hidden_files;
+                let dual_pane = &mut self.settings.dual_pane;
+                let loader = &mut self.loader;
+                let tab = match &mut self.second_pane {
+                    Some(pane) if self.right_pane_active => pane,
+                    _ => &mut self.tabs[self.active_tab],
+                };
+                let refresh = ui.horizontal(|ui| {
+                    let back_button = nav_button(
+                        ui,
+                        egui_phosphor::regular::ARROW_LEFT,
+                        tab.can_go_back(),
+                        "Go back (hold or right-click for history)",
+                    );
+                    let forward_button = nav_button(
+                        ui,
+                        egui_phosphor::regular::ARROW_RIGHT,
+                        tab.can_go_forward(),
+                        "Go forward (hold or right-click for history)",
+                    );
+                    let up_button = nav_button(
+                        ui,
+                        egui_phosphor::regular::ARROW_UP,
+                        tab.can_go_up(),
+                        "Go to parent folder",
+                    );
+                    let refresh_button =
+                        nav_button(ui, egui_phosphor::regular::ARROW_CLOCKWISE, true, "Refresh");
 
-                    if back_button.clicked() {
-                        self.go_back();
+                    let jump = history_menu(ui, &back_button, &tab.history, tab.history_index)
+                        .or_else(|| {
+                            history_menu(ui, &forward_button, &tab.history, tab.history_index)
+                        });
+
+                    let mut refresh = false;
+                    if let Some(index) = jump {
+                        tab.jump_to_history(index, loader);
+                    } else if back_button.clicked() {
+                        tab.go_back(loader);
+                    } else if forward_button.clicked() {
+                        tab.go_forward(loader);
+                    } else if up_button.clicked() {
+                        tab.go_up(loader);
+                    } else if refresh_button.clicked() {
+                        refresh = true;
                     }
 
-                    ui.add_space(8.0);
-
-                    ui.label(
-                        egui::RichText::new(
-                            self.current_root
-                                .to_string_lossy()
-                                .replace('\\', "/")
-                                .replace(&format!("/home/{}", whoami::username()).to_string(), "~"),
-                        )
-                        .monospace()
-                        .weak(),
-                    );
+                    // Each tab can be shown its own way
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let (icon, hover, other) = match tab.view {
+                            View::List => (
+                                egui_phosphor::regular::SQUARES_FOUR,
+                                "Show as grid",
+                                View::Grid,
+                            ),
+                            View::Grid => {
+                                (egui_phosphor::regular::LIST, "Show as list", View::List)
+                            }
+                        };
+                        if nav_button(ui, icon, true, hover).clicked() {
+                            tab.view = other;
+                        }
+                        if ui
+                            .add(egui::Button::selectable(
+                                *dual_pane,
+                                egui::RichText::new(egui_phosphor::regular::COLUMNS).size(20.0),
+                            ))
+                            .on_hover_text("Two panes side by side")
+                            .clicked()
+                        {
+                            *dual_pane = !*dual_pane;
+                        }
+
+                        ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                            ui.add_space(8.0);
+
+                            if let Some(path) =
+                                tab.path_bar.show(ui, &tab.current_root, show_hidden)
+                            {
+                                // Picking the shown folder leaves the trash
+                                tab.show_trash = false;
+                                tab.go_to_directory(&path, loader);
+                            }
+                        });
+                    });
+
+                    refresh
                 });
+
+                if refresh.inner {
+                    self.refresh();
+                }
             });
 
+            if !self.jobs.jobs().is_empty() {
+                egui::TopBottomPanel::bottom("operations").show_inside(ui, |ui| {
+                    ui::jobs::show_jobs_panel(ui, &mut self.jobs);
+                });
+            }
+
             egui::CentralPanel::default().show_inside(ui, |ui| {
-                egui::ScrollArea::vertical()
-                    .auto_shrink([false, true])
-                    .show(ui, |ui| {
-                        if let Some(target) =
-                            views::render_file_node(ui, &mut self.file_tree, &self.settings)
+                let left = &mut self.tabs[self.active_tab];
+                let mut responses = Vec::new();
+
+                if let Some(right) = &mut self.second_pane {
+                    left.view_state.inactive = self.right_pane_active;
+                    right.view_state.inactive = !self.right_pane_active;
+
+                    ui.columns(2, |columns| {
+                        for (is_right, (ui, tab)) in [false, true]
+                            .into_iter()
+                            .zip(columns.iter_mut().zip([left, right]))
                         {
-                            self.go_to_directory(&target);
+                            let rect = ui.max_rect();
+                            let response = show_tab_contents(
+                                ui,
+                                tab,
+                                &self.settings,
+                                self.clipboard.as_ref(),
+                                &self.trash_entries,
+                            );
+                            responses.push((is_right, response));
+
+                            // Clicking into a pane makes it the active one
+                            if ui.input(|i| i.pointer.any_pressed())
+                                && ui.rect_contains_pointer(rect)
+                            {
+                                self.right_pane_active = is_right;
+                            }
+                            if is_right == self.right_pane_active {
+                                ui.painter().rect_stroke(
+                                    rect,
+                                    4.0,
+                                    ui.visuals().selection.stroke,
+                                    egui::StrokeKind::Inside,
+                                );
+                            }
                         }
                     });
+                } else {
+                    left.view_state.inactive = false;
+                    let response = show_tab_contents(
+                        ui,
+                        left,
+                        &self.settings,
+                        self.clipboard.as_ref(),
+                        &self.trash_entries,
+                    );
+                    responses.push((false, response));
+                }
+
+                for (is_right, response) in responses {
+                    for action in response.actions {
+                        self.run_action(action);
+                    }
+                    let pane = match &mut self.second_pane {
+                        Some(pane) if is_right => pane,
+                        _ => &mut self.tabs[self.active_tab],
+                    };
+                    if let Some(target) = response.nav_request {
+                        pane.go_to_directory(&target, &mut self.loader);
+                    }
+                    if let Some(target) = response.new_tab_request {
+                        self.open_tab(target, false);
+                    }
+                    if let Some((key, descending)) = response.sort_request {
+                        self.settings.sort_items = true;
+                        self.settings.sort_key = key;
+                        self.settings.sort_descending = descending;
+                    }
+                    if let Some(columns) = response.columns_request {
+                        self.settings.list_columns = columns;
+                    }
+                }
             });
         });
 
+        if let Some(action) = self.notifications.show(ctx) {
+            self.run_action(action);
+        }
+
+        // Tab also moved egui's keyboard focus onto the first widget, take it back
+        if switched_pane && let Some(id) = ctx.memory(|m| m.focused()) {
+            ctx.memory_mut(|m| m.surrender_focus(id));
+        }
+
         if show_settings_toggle {
             self.show_settings = !self.show_settings;
         }
+
+        let config = Config {
+            settings: self.settings.clone(),
+            favorites: self.places.favorites().to_vec(),
+        };
+        if let Err(error) = self.config_file.save(&config) {
+            self.notifications.error(error, None);
+        }
     }
 }
@@ -0,0 +1,241 @@
+use eframe::egui;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::file_system::error::{FsError, FsOperation, FsResult};
+use crate::ui::settings::Settings;
+
+// Bumped whenever the layout of the file changes, with a step added to MIGRATIONS
+const CONFIG_VERSION: usize = 1;
+
+// Each step brings a file from the version at its index to the next one
+const MIGRATIONS: [fn(&mut toml::Table); CONFIG_VERSION] = [migrate_v0];
+
+// Editors write in several steps, wait for them to finish before reading
+const QUIET_PERIOD: Duration = Duration::from_millis(100);
+
+const HEADER: &str = "# Rustplorer configuration, changes are picked up while it runs\n\n";
+
+// Everything kept across launches
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub settings: Settings,
+    pub favorites: Vec<PathBuf>,
+}
+
+pub fn home_dir() -> PathBuf {
+    PathBuf::from(std::env::var_os("HOME").unwrap_or_default())
+}
+
+pub fn config_home() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| home_dir().join(".config"))
+}
+
+pub fn default_path() -> PathBuf {
+    config_home().join("rustplorer").join("config.toml")
+}
+
+// Files without a version were written by hand with the settings at the top level
+fn migrate_v0(table: &mut toml::Table) {
+    let keys: Vec<String> = table
+        .keys()
+        .filter(|key| *key != "settings" && *key != "favorites")
+        .cloned()
+        .collect();
+    let mut settings = match table.remove("settings") {
+        Some(toml::Value::Table(settings)) => settings,
+        _ => toml::Table::new(),
+    };
+    for key in keys {
+        if let Some(value) = table.remove(&key) {
+            settings.entry(key).or_insert(value);
+        }
+    }
+    table.insert("settings".to_string(), toml::Value::Table(settings));
+}
+
+// The config and the version the file was written for
+fn parse(contents: &str) -> io::Result<(Config, usize)> {
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+
+    let mut table: toml::Table = contents.parse().map_err(invalid)?;
+    let version = match table.remove("version") {
+        Some(version) => version
+            .as_integer()
+            .and_then(|version| usize::try_from(version).ok())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "version must be a number")
+            })?,
+        None => 0,
+    };
+    for migrate in MIGRATIONS.iter().skip(version) {
+        migrate(&mut table);
+    }
+
+    let config = toml::Value::Table(table).try_into().map_err(invalid)?;
+    Ok((config, version))
+}
+
+fn serialize(config: &Config) -> io::Result<String> {
+    let mut table = toml::Table::try_from(config).map_err(io::Error::other)?;
+    table.insert(
+        "version".to_string(),
+        toml::Value::Integer(CONFIG_VERSION as i64),
+    );
+    let contents = toml::to_string_pretty(&table).map_err(io::Error::other)?;
+    Ok(format!("{}{}", HEADER, contents))
+}
+
+// The config file: read at startup, written when the config changes and read again when
+// something else edits it
+pub struct ConfigFile {
+    ctx: egui::Context,
+    path: PathBuf,
+    // What the file holds as far as we know, so our own writes aren't read back
+    saved: Config,
+    // Whether the file was last read fine. One that's broken or from a newer version isn't
+    // written over, so nothing in it is lost.
+    writable: bool,
+    // Something to tell about the last read that didn't stop it
+    notice: Option<FsError>,
+    watcher: Option<RecommendedWatcher>,
+    watching: bool,
+    event_rx: Receiver<notify::Result<notify::Event>>,
+    last_event: Option<Instant>,
+}
+
+impl ConfigFile {
+    pub fn new(ctx: &egui::Context, path: PathBuf) -> Self {
+        let (event_tx, event_rx) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = event_tx.send(event);
+            repaint_ctx.request_repaint();
+        })
+        .map_err(|e| eprintln!("Failed to start config watcher: {}", e))
+        .ok();
+
+        let mut file = Self {
+            ctx: ctx.clone(),
+            path,
+            saved: Config::default(),
+            writable: true,
+            notice: None,
+            watcher,
+            watching: false,
+            event_rx,
+            last_event: None,
+        };
+        file.watch();
+        file
+    }
+
+    // Watch the folder rather than the file, editors replace the file on save. The folder
+    // may only exist after the first save.
+    fn watch(&mut self) {
+        let (Some(watcher), Some(parent)) = (self.watcher.as_mut(), self.path.parent()) else {
+            return;
+        };
+        if !self.watching {
+            self.watching = watcher.watch(parent, RecursiveMode::NonRecursive).is_ok();
+        }
+    }
+
+    // A missing file is the default config
+    pub fn load(&mut self) -> FsResult<Config> {
+        let loaded = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok((Config::default(), CONFIG_VERSION))
+            }
+            Err(e) => Err(e),
+        }
+        .map_err(|e| FsError::new(FsOperation::ReadConfig, &self.path, e));
+
+        let (config, version) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.writable = false;
+                return Err(e);
+            }
+        };
+
+        self.writable = version <= CONFIG_VERSION;
+        if !self.writable {
+            self.notice = Some(FsError::new(
+                FsOperation::ReadConfig,
+                &self.path,
+                io::Error::other(format!(
+                    "version {} is newer than {}, unknown keys are ignored and changes aren't saved",
+                    version, CONFIG_VERSION
+                )),
+            ));
+        }
+        self.saved = config.clone();
+        Ok(config)
+    }
+
+    pub fn take_notice(&mut self) -> Option<FsError> {
+        self.notice.take()
+    }
+
+    // Write the config if it differs from the file. A failed write isn't retried until
+    // the config changes again, a file that couldn't be read isn't written until it's fixed.
+    pub fn save(&mut self, config: &Config) -> FsResult<()> {
+        if !self.writable || *config == self.saved {
+            return Ok(());
+        }
+        self.saved = config.clone();
+
+        let path = &self.path;
+        serialize(config)
+            .and_then(|contents| {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let tmp_path = path.with_extension("toml.tmp");
+                std::fs::write(&tmp_path, contents)?;
+                std::fs::rename(&tmp_path, path)
+            })
+            .map_err(|e| FsError::new(FsOperation::SaveConfig, path, e))?;
+
+        self.watch();
+        Ok(())
+    }
+
+    // The config after an outside edit, once the edit is over
+    pub fn poll(&mut self) -> Option<FsResult<Config>> {
+        while let Ok(event) = self.event_rx.try_recv() {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(event) if !event.paths.iter().any(|path| path == &self.path) => {}
+                Ok(_) => self.last_event = Some(Instant::now()),
+                Err(e) => eprintln!("Config watcher error: {}", e),
+            }
+        }
+
+        let last = self.last_event?;
+        if last.elapsed() < QUIET_PERIOD {
+            self.ctx
+                .request_repaint_after(QUIET_PERIOD.saturating_sub(last.elapsed()));
+            return None;
+        }
+        self.last_event = None;
+
+        // After a failed read the app went on with its own config, the fixed file replaces it
+        let previous = self.writable.then(|| self.saved.clone());
+        match self.load() {
+            Ok(config) if Some(&config) == previous.as_ref() => None,
+            result => Some(result),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsOperation {
+    ReadDir,
+    Delete,
+    Move,
+    Copy,
+    Rename,
+    Create,
+    Trash,
+    Restore,
+    EmptyTrash,
+    ReadConfig,
+    SaveConfig,
+    SaveJournal,
+}
+
+impl FsOperation {
+    pub fn failure_title(&self) -> &'static str {
+        match self {
+            FsOperation::ReadDir => "Couldn't open folder",
+            FsOperation::Delete => "Couldn't delete",
+            FsOperation::Move => "Couldn't move",
+            FsOperation::Copy => "Couldn't copy",
+            FsOperation::Rename => "Couldn't rename",
+            FsOperation::Create => "Couldn't create",
+            FsOperation::Trash => "Couldn't move to trash",
+            FsOperation::Restore => "Couldn't restore",
+            FsOperation::EmptyTrash => "Couldn't empty trash",
+            FsOperation::ReadConfig => "Couldn't read config",
+            FsOperation::SaveConfig => "Couldn't save config",
+            FsOperation::SaveJournal => "Couldn't save undo history",
+        }
+    }
+}
+
+// A failed filesystem operation, with the path it failed on
+#[derive(Debug)]
+pub struct FsError {
+    pub operation: FsOperation,
+    pub path: PathBuf,
+    pub source: std::io::Error,
+}
+
+pub type FsResult<T> = Result<T, FsError>;
+
+impl FsError {
+    pub fn new(operation: FsOperation, path: &Path, source: std::io::Error) -> Self {
+        Self {
+            operation,
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl std::fmt::Display for FsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} '{}': {}",
+            self.operation.failure_title(),
+            self.path.display(),
+            self.source
+        )
+    }
+}
+
+impl std::error::Error for FsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
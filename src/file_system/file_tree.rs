@@ -1,9 +1,16 @@
+use super::metadata::{FileKind, FileMetadata};
+use crate::utils::sorting::{self, Collation, NameKey};
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct FileNode {
     pub name: String,
     pub path: std::path::PathBuf,
     pub is_dir: bool,
     pub children: Option<Vec<FileNode>>,
+    pub loading: bool,
+    pub load_error: Option<String>,
+    pub metadata: FileMetadata,
+    pub name_key: NameKey,
 }
 
 impl FileNode {
@@ -12,54 +19,134 @@ impl FileNode {
             .file_name()
             .map(|os_str| os_str.to_string_lossy().into_owned())
             .unwrap_or_else(|| path.to_string_lossy().into_owned());
-        let is_dir = path.is_dir();
+        let metadata = FileMetadata::read(path);
+        let is_dir =
+            metadata.kind == FileKind::Dir || (metadata.kind == FileKind::Symlink && path.is_dir());
         let children = if is_dir { None } else { Some(Vec::new()) };
+        let name_key = NameKey::new(&name);
 
         Self {
             name,
             path: path.to_path_buf(),
             is_dir,
             children,
+            loading: false,
+            load_error: None,
+            metadata,
+            name_key,
         }
     }
 
-    pub fn ensure_children_loaded(&mut self) {
-        if self.is_dir && self.children.is_none() {
-            let loaded_children = std::fs::read_dir(&self.path)
-                .ok()
-                .into_iter()
-                .flatten()
-                .filter_map(|entry| entry.ok())
-                .map(|entry| FileNode::new(&entry.path()))
-                .collect::<Vec<_>>();
+    // Find a loaded node by path, starting at this one
+    pub fn find_mut(&mut self, path: &std::path::Path) -> Option<&mut FileNode> {
+        if self.path == path {
+            return Some(self);
+        }
+        if !path.starts_with(&self.path) {
+            return None;
+        }
+
+        self.children
+            .as_mut()?
+            .iter_mut()
+            .find_map(|child| child.find_mut(path))
+    }
+
+    // Insert a child at its sorted position
+    pub fn insert_child(&mut self, child: FileNode, collation: Collation) {
+        let children = self.children.get_or_insert_with(Vec::new);
+        let idx = children
+            .binary_search_by(|c| child_order(c, &child, collation))
+            .unwrap_or_else(|idx| idx);
+        children.insert(idx, child);
+    }
 
-            let mut dirs = Vec::new();
-            let mut files = Vec::new();
-            for child in loaded_children {
-                if child.is_dir {
-                    dirs.push(child);
-                } else {
-                    files.push(child);
+    // Bring a single child in line with what is on disk
+    pub fn refresh_child(&mut self, path: &std::path::Path, collation: Collation) {
+        let Some(children) = self.children.as_mut() else {
+            return;
+        };
+
+        let existing = children.iter().position(|c| c.path == path);
+        let exists = std::fs::symlink_metadata(path).is_ok();
+
+        match (existing, exists) {
+            (Some(idx), true) => {
+                let mut updated = FileNode::new(path);
+                // Keep already loaded contents so nested views don't reset, and the entry
+                // count only the loader works out
+                if updated.is_dir == children[idx].is_dir {
+                    updated.children = children[idx].children.take();
+                    updated.loading = children[idx].loading;
+                    updated.metadata.item_count = children[idx].metadata.item_count;
                 }
+                children[idx] = updated;
+            }
+            (Some(idx), false) => {
+                children.remove(idx);
             }
-            dirs.sort_by(|a, b| a.name.cmp(&b.name));
-            files.sort_by(|a, b| a.name.cmp(&b.name));
-            dirs.extend(files);
+            (None, true) => self.insert_child(FileNode::new(path), collation),
+            (None, false) => {}
+        }
+    }
 
-            self.children = Some(dirs);
+    // Point an entry at its new path after a rename, keeping its place in the list and
+    // whatever of it is already loaded
+    pub fn rename_entry(&mut self, from: &std::path::Path, to: &std::path::Path) {
+        if let Some(node) = self.find_mut(from) {
+            let mut updated = FileNode::new(to);
+            if updated.is_dir == node.is_dir {
+                node.move_under(from, to);
+                updated.children = node.children.take();
+                updated.metadata.item_count = node.metadata.item_count;
+            }
+            *node = updated;
         }
     }
 
-    pub fn _refresh_children(&mut self) {
-        if self.is_dir {
+    // Swap the start of this node's and its descendants' paths after a folder was renamed.
+    // Listings still on their way are for the old paths, those folders count as not loaded.
+    fn move_under(&mut self, from: &std::path::Path, to: &std::path::Path) {
+        if let Ok(rest) = self.path.strip_prefix(from) {
+            self.path = to.join(rest);
+        }
+        if self.loading {
             self.children = None;
-            self.ensure_children_loaded();
+            self.loading = false;
+        }
+        if let Some(children) = self.children.as_mut() {
+            for child in children {
+                child.move_under(from, to);
+            }
+        }
+    }
+
+    // Refresh the entry for a path anywhere in the loaded tree
+    pub fn refresh_path(&mut self, path: &std::path::Path, collation: Collation) {
+        if let Some(parent) = path.parent().and_then(|parent| self.find_mut(parent)) {
+            parent.refresh_child(path, collation);
         }
     }
+
+    // Put every loaded folder back in order after the collation changed
+    pub fn resort(&mut self, collation: Collation) {
+        if let Some(children) = self.children.as_mut() {
+            children.sort_by(|a, b| child_order(a, b, collation));
+            for child in children {
+                child.resort(collation);
+            }
+        }
+    }
+}
+
+// Folders first, each group by name, compared the way the views do
+pub fn child_order(a: &FileNode, b: &FileNode, collation: Collation) -> std::cmp::Ordering {
+    b.is_dir
+        .cmp(&a.is_dir)
+        .then_with(|| sorting::compare_names(a, b, collation))
 }
 
-pub fn _build_file_tree(root: &std::path::Path) -> FileNode {
-    let mut node = FileNode::new(root);
-    node.ensure_children_loaded();
-    node
+pub fn sort_children(mut children: Vec<FileNode>, collation: Collation) -> Vec<FileNode> {
+    children.sort_by(|a, b| child_order(a, b, collation));
+    children
 }
@@ -46,3 +46,75 @@ pub fn get_file_icon(filename: &str, is_folder: bool) -> &'static str {
         _ => egui_nerdfonts::regular::FILE,
     }
 }
+
+// Name of the kind of file, for the same families get_file_icon tells apart
+pub fn get_file_type(filename: &str, is_folder: bool) -> &'static str {
+    if is_folder {
+        return "Folder";
+    }
+
+    let extension = filename.rsplit('.').next().unwrap_or("");
+    match extension {
+        "rs" => "Rust source",
+        "toml" => "TOML",
+        "lock" => "Lock file",
+        "js" | "jsx" => "JavaScript",
+        "ts" | "tsx" => "TypeScript",
+        "py" => "Python",
+        "html" => "HTML",
+        "css" | "scss" | "sass" => "Stylesheet",
+        "json" => "JSON",
+        "md" => "Markdown",
+        "xml" => "XML",
+        "zip" | "tar" | "gz" => "Archive",
+        "jpg" | "jpeg" | "png" | "gif" | "svg" => "Image",
+        "mp4" | "avi" | "mov" => "Video",
+        "mp3" | "wav" | "ogg" => "Audio",
+        "gitignore" => "Git",
+        "pdf" => "PDF",
+        "c" => "C source",
+        "cpp" => "C++ source",
+        _ => "File",
+    }
+}
+
+// Placeholder for metadata the filesystem couldn't provide
+pub const UNAVAILABLE: &str = "—";
+
+pub fn format_optional_size(bytes: Option<u64>) -> String {
+    bytes.map_or_else(|| UNAVAILABLE.to_string(), format_file_size)
+}
+
+pub fn format_date(time: Option<std::time::SystemTime>) -> String {
+    match time {
+        Some(time) => {
+            let datetime: chrono::DateTime<chrono::Utc> = time.into();
+            datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+        }
+        None => UNAVAILABLE.to_string(),
+    }
+}
+
+// Render a unix mode as "rwxr-xr-x"
+pub fn format_permissions(mode: Option<u32>) -> String {
+    let Some(mode) = mode else {
+        return UNAVAILABLE.to_string();
+    };
+
+    const FLAGS: [(u32, char); 9] = [
+        (0o400, 'r'),
+        (0o200, 'w'),
+        (0o100, 'x'),
+        (0o040, 'r'),
+        (0o020, 'w'),
+        (0o010, 'x'),
+        (0o004, 'r'),
+        (0o002, 'w'),
+        (0o001, 'x'),
+    ];
+
+    FLAGS
+        .iter()
+        .map(|&(bit, c)| if mode & bit != 0 { c } else { '-' })
+        .collect()
+}
@@ -0,0 +1,774 @@
+use super::error::{FsError, FsOperation, FsResult};
+use super::operations::{self, FileAction};
+use super::trash::{self, TrashEntry};
+use eframe::egui;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const WORKER_COUNT: usize = 2;
+const CHUNK_SIZE: usize = 1024 * 1024;
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobKind {
+    Copy { to_dir: PathBuf },
+    Move { to_dir: PathBuf },
+    Delete,
+    Trash,
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+impl JobKind {
+    pub fn title(&self, sources: &[PathBuf]) -> String {
+        let items = match sources {
+            [source] => format!("'{}'", display_name(source)),
+            sources => format!("{} items", sources.len()),
+        };
+        match self {
+            JobKind::Copy { to_dir } => format!("Copying {} to '{}'", items, display_name(to_dir)),
+            JobKind::Move { to_dir } => format!("Moving {} to '{}'", items, display_name(to_dir)),
+            JobKind::Delete => format!("Deleting {}", items),
+            JobKind::Trash => format!("Moving {} to the trash", items),
+        }
+    }
+
+    // The action that redoes this job for a single source that failed
+    pub fn retry_action(&self, source: &Path) -> FileAction {
+        let paths = vec![source.to_path_buf()];
+        match self {
+            JobKind::Copy { to_dir } => FileAction::Copy {
+                paths,
+                to_dir: to_dir.clone(),
+            },
+            JobKind::Move { to_dir } => FileAction::Move {
+                paths,
+                to_dir: to_dir.clone(),
+            },
+            JobKind::Delete => FileAction::Delete(paths),
+            JobKind::Trash => FileAction::Trash(paths),
+        }
+    }
+}
+
+// Why a job runs: the user asked for it, or it carries out an undo or redo step of the journal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobOrigin {
+    User,
+    Undo,
+    Redo,
+}
+
+// What to do when a copied or moved item's name is already taken in the target folder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    Replace,
+    Skip,
+    KeepBoth,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    // What has the name: the target, or an earlier source of the same transfer
+    pub existing: PathBuf,
+}
+
+fn planned_target(kind: &JobKind, source: &Path) -> Option<FsResult<PathBuf>> {
+    match kind {
+        JobKind::Copy { to_dir } => Some(operations::copy_target(source, to_dir)),
+        JobKind::Move { to_dir } => Some(operations::move_target(source, to_dir)),
+        JobKind::Delete | JobKind::Trash => None,
+    }
+}
+
+// Drop moves onto themselves and find the sources whose target name is taken, either in
+// the target folder or by another source with the same name.
+// Sources that will fail anyway are left for the job to report.
+pub fn plan_transfer(kind: &JobKind, sources: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<Conflict>) {
+    let mut kept = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut planned: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+    for source in sources {
+        match planned_target(kind, &source) {
+            Some(Ok(target)) if target == source => continue,
+            Some(Ok(target)) => {
+                let existing = if std::fs::symlink_metadata(&target).is_ok() {
+                    Some(target.clone())
+                } else {
+                    planned.get(&target).cloned()
+                };
+                planned.entry(target.clone()).or_insert(source.clone());
+                if let Some(existing) = existing {
+                    conflicts.push(Conflict {
+                        source: source.clone(),
+                        target,
+                        existing,
+                    });
+                }
+            }
+            _ => {}
+        }
+        kept.push(source);
+    }
+
+    (kept, conflicts)
+}
+
+// What happened to one source of a job
+#[derive(Debug, Clone)]
+pub enum JobOutcome {
+    Copied { from: PathBuf, to: PathBuf },
+    Moved { from: PathBuf, to: PathBuf },
+    Deleted(PathBuf),
+    Trashed(TrashEntry),
+}
+
+// Counters shared between a job's worker and the UI. Items are files, folders and links.
+#[derive(Default)]
+pub struct JobProgress {
+    pub bytes_done: AtomicU64,
+    pub bytes_total: AtomicU64,
+    pub items_done: AtomicU64,
+    pub items_total: AtomicU64,
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+    started_at: Mutex<Option<Instant>>,
+    current: Mutex<Option<PathBuf>>,
+}
+
+impl JobProgress {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn has_started(&self) -> bool {
+        self.started_at.lock().unwrap().is_some()
+    }
+
+    pub fn current(&self) -> Option<PathBuf> {
+        self.current.lock().unwrap().clone()
+    }
+
+    // Called between files and chunks: blocks while paused, fails once cancelled
+    fn checkpoint(&self) -> std::io::Result<()> {
+        while self.is_paused() && !self.is_cancelled() {
+            std::thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+        if self.is_cancelled() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "cancelled",
+            ));
+        }
+        Ok(())
+    }
+
+    // Share of the work done, by bytes when there are any to copy
+    pub fn fraction(&self) -> f32 {
+        let bytes_total = self.bytes_total.load(Ordering::Relaxed);
+        let (done, total) = if bytes_total > 0 {
+            (self.bytes_done.load(Ordering::Relaxed), bytes_total)
+        } else {
+            (
+                self.items_done.load(Ordering::Relaxed),
+                self.items_total.load(Ordering::Relaxed),
+            )
+        };
+        if total == 0 {
+            return 0.0;
+        }
+        (done as f64 / total as f64).min(1.0) as f32
+    }
+}
+
+// A queued or running job as shown in the operations panel
+pub struct JobInfo {
+    pub id: u64,
+    pub title: String,
+    pub progress: Arc<JobProgress>,
+    paused_since: Option<Instant>,
+    paused_for: Duration,
+}
+
+impl JobInfo {
+    // Remaining time extrapolated from the progress so far, not counting pauses
+    pub fn eta(&self) -> Option<Duration> {
+        let started_at = (*self.progress.started_at.lock().unwrap())?;
+        let mut paused_for = self.paused_for;
+        if let Some(paused_since) = self.paused_since {
+            paused_for += paused_since.elapsed();
+        }
+        let elapsed = started_at.elapsed().saturating_sub(paused_for);
+
+        let fraction = self.progress.fraction() as f64;
+        if fraction < 0.01 || elapsed < Duration::from_secs(1) {
+            return None;
+        }
+        Some(elapsed.mul_f64((1.0 - fraction) / fraction))
+    }
+}
+
+struct Job {
+    id: u64,
+    kind: JobKind,
+    origin: JobOrigin,
+    sources: Vec<PathBuf>,
+    resolutions: HashMap<PathBuf, Resolution>,
+    // Exact targets of sources, in place of their name in the kind's folder
+    targets: HashMap<PathBuf, PathBuf>,
+    progress: Arc<JobProgress>,
+}
+
+pub struct FinishedJob {
+    pub kind: JobKind,
+    pub origin: JobOrigin,
+    pub outcomes: Vec<JobOutcome>,
+    // The source each error happened under, for retrying
+    pub errors: Vec<(PathBuf, FsError)>,
+    pub cancelled: bool,
+}
+
+// Runs copies, moves, deletes and trashing on worker threads, oldest jobs first
+pub struct JobQueue {
+    job_tx: Sender<Job>,
+    finished_rx: Receiver<(u64, FinishedJob)>,
+    jobs: Vec<JobInfo>,
+    next_id: u64,
+}
+
+impl JobQueue {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (job_tx, job_rx) = mpsc::channel::<Job>();
+        let (finished_tx, finished_rx) = mpsc::channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        for i in 0..WORKER_COUNT {
+            let job_rx = Arc::clone(&job_rx);
+            let finished_tx = finished_tx.clone();
+            let ctx = ctx.clone();
+
+            std::thread::Builder::new()
+                .name(format!("file-jobs-{i}"))
+                .spawn(move || {
+                    loop {
+                        let job = {
+                            let rx = job_rx.lock().unwrap();
+                            rx.recv()
+                        };
+                        match job {
+                            Ok(job) => {
+                                let id = job.id;
+                                let _ = finished_tx.send((id, run_job(job)));
+                                ctx.request_repaint();
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                })
+                .expect("Couldn't spawn file job thread.");
+        }
+
+        Self {
+            job_tx,
+            finished_rx,
+            jobs: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    // Sources resolved with Skip should be left out of sources already
+    pub fn submit(
+        &mut self,
+        kind: JobKind,
+        sources: Vec<PathBuf>,
+        resolutions: HashMap<PathBuf, Resolution>,
+    ) {
+        self.queue(kind, JobOrigin::User, sources, resolutions, HashMap::new());
+    }
+
+    // Copy or move each source to exactly the path paired with it, for undo and redo.
+    // Taken targets fail rather than being replaced.
+    pub fn submit_step(
+        &mut self,
+        kind: JobKind,
+        origin: JobOrigin,
+        pairs: Vec<(PathBuf, PathBuf)>,
+    ) {
+        let sources = pairs.iter().map(|(source, _)| source.clone()).collect();
+        self.queue(
+            kind,
+            origin,
+            sources,
+            HashMap::new(),
+            pairs.into_iter().collect(),
+        );
+    }
+
+    fn queue(
+        &mut self,
+        kind: JobKind,
+        origin: JobOrigin,
+        sources: Vec<PathBuf>,
+        resolutions: HashMap<PathBuf, Resolution>,
+        targets: HashMap<PathBuf, PathBuf>,
+    ) {
+        if sources.is_empty() {
+            return;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let progress = Arc::new(JobProgress::default());
+
+        self.jobs.push(JobInfo {
+            id,
+            title: kind.title(&sources),
+            progress: Arc::clone(&progress),
+            paused_since: None,
+            paused_for: Duration::ZERO,
+        });
+
+        let _ = self.job_tx.send(Job {
+            id,
+            kind,
+            origin,
+            sources,
+            resolutions,
+            targets,
+            progress,
+        });
+    }
+
+    pub fn jobs(&self) -> &[JobInfo] {
+        &self.jobs
+    }
+
+    pub fn set_paused(&mut self, id: u64, paused: bool) {
+        let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) else {
+            return;
+        };
+        job.progress.paused.store(paused, Ordering::Relaxed);
+
+        match (paused, job.paused_since) {
+            (true, None) => job.paused_since = Some(Instant::now()),
+            (false, Some(paused_since)) => {
+                job.paused_for += paused_since.elapsed();
+                job.paused_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    // Stop a job after the item it is on, items already done stay done
+    pub fn cancel(&mut self, id: u64) {
+        if let Some(job) = self.jobs.iter().find(|job| job.id == id) {
+            job.progress.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Jobs that finished since the last frame
+    pub fn poll(&mut self) -> Vec<FinishedJob> {
+        let mut finished = Vec::new();
+        while let Ok((id, job)) = self.finished_rx.try_recv() {
+            self.jobs.retain(|info| info.id != id);
+            finished.push(job);
+        }
+        finished
+    }
+}
+
+fn run_job(job: Job) -> FinishedJob {
+    let progress = &job.progress;
+    *progress.started_at.lock().unwrap() = Some(Instant::now());
+
+    for source in &job.sources {
+        if progress.is_cancelled() {
+            break;
+        }
+        let (items, bytes) = match job.kind {
+            JobKind::Copy { .. } | JobKind::Delete => measure(source, progress),
+            // Renames take the same time no matter the size
+            JobKind::Move { .. } | JobKind::Trash => (1, 0),
+        };
+        progress.items_total.fetch_add(items, Ordering::Relaxed);
+        progress.bytes_total.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    let mut finished = FinishedJob {
+        kind: job.kind.clone(),
+        origin: job.origin,
+        outcomes: Vec::new(),
+        errors: Vec::new(),
+        cancelled: false,
+    };
+
+    for source in &job.sources {
+        if progress.checkpoint().is_err() {
+            finished.cancelled = true;
+            break;
+        }
+        *progress.current.lock().unwrap() = Some(source.clone());
+
+        let resolution = job.resolutions.get(source).copied();
+        let target = job.targets.get(source).map(PathBuf::as_path);
+        match run_source(&job.kind, source, target, resolution, progress) {
+            Ok(outcome) => finished.outcomes.push(outcome),
+            Err(_) if progress.is_cancelled() => {
+                finished.cancelled = true;
+                break;
+            }
+            Err(e) => finished.errors.push((source.clone(), e)),
+        }
+    }
+
+    finished
+}
+
+fn run_source(
+    kind: &JobKind,
+    source: &Path,
+    target: Option<&Path>,
+    resolution: Option<Resolution>,
+    progress: &JobProgress,
+) -> FsResult<JobOutcome> {
+    match kind {
+        JobKind::Copy { to_dir } => {
+            let target = match target {
+                Some(target) => target.to_path_buf(),
+                None => operations::copy_target(source, to_dir)?,
+            };
+            let target = place(target, resolution, FsOperation::Copy)?;
+
+            replacing(&target, FsOperation::Copy, || {
+                let target_existed = std::fs::symlink_metadata(&target).is_ok();
+                // Don't leave half a copy behind
+                copy_tree(source, &target, progress).inspect_err(|_| {
+                    if !target_existed {
+                        let _ = remove_tree(&target, &JobProgress::default());
+                    }
+                })
+            })?;
+            Ok(JobOutcome::Copied {
+                from: source.to_path_buf(),
+                to: target,
+            })
+        }
+        JobKind::Move { to_dir } => {
+            let target = match target {
+                Some(target) => target.to_path_buf(),
+                None => operations::move_target(source, to_dir)?,
+            };
+            let target = place(target, resolution, FsOperation::Move)?;
+
+            replacing(&target, FsOperation::Move, || {
+                match std::fs::rename(source, &target) {
+                    Ok(()) => {
+                        progress.items_done.fetch_add(1, Ordering::Relaxed);
+                        Ok(())
+                    }
+                    // rename can't cross filesystems, e.g. onto a USB stick or tmpfs
+                    Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                        move_across_devices(source, &target, progress)
+                    }
+                    Err(e) => Err(FsError::new(FsOperation::Move, source, e)),
+                }
+            })?;
+            Ok(JobOutcome::Moved {
+                from: source.to_path_buf(),
+                to: target,
+            })
+        }
+        JobKind::Delete => {
+            remove_tree(source, progress)?;
+            Ok(JobOutcome::Deleted(source.to_path_buf()))
+        }
+        JobKind::Trash => {
+            let entry = trash::trash_path(source)?;
+            progress.items_done.fetch_add(1, Ordering::Relaxed);
+            Ok(JobOutcome::Trashed(entry))
+        }
+    }
+}
+
+fn already_exists(operation: FsOperation, path: &Path) -> FsError {
+    FsError::new(
+        operation,
+        path,
+        std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "an item with the same name already exists",
+        ),
+    )
+}
+
+// Apply a conflict resolution to a target. Without one a taken name is an error, never overwritten.
+fn place(
+    target: PathBuf,
+    resolution: Option<Resolution>,
+    operation: FsOperation,
+) -> FsResult<PathBuf> {
+    let is_taken = std::fs::symlink_metadata(&target).is_ok();
+    match resolution {
+        Some(Resolution::KeepBoth) if is_taken => {
+            let (Some(dir), Some(name)) = (target.parent(), target.file_name()) else {
+                return Ok(target);
+            };
+            Ok(operations::unique_path(dir, &name.to_string_lossy()))
+        }
+        Some(Resolution::Replace) => Ok(target),
+        _ if is_taken => Err(already_exists(operation, &target)),
+        _ => Ok(target),
+    }
+}
+
+// Run an operation onto a taken target, keeping the old item aside until the operation succeeded
+fn replacing(
+    target: &Path,
+    operation: FsOperation,
+    run: impl FnOnce() -> FsResult<()>,
+) -> FsResult<()> {
+    let (Some(dir), Some(name)) = (target.parent(), target.file_name()) else {
+        return run();
+    };
+    if std::fs::symlink_metadata(target).is_err() {
+        return run();
+    }
+
+    let aside = operations::unique_path(dir, &format!(".{}.replaced", name.to_string_lossy()));
+    std::fs::rename(target, &aside).map_err(|e| FsError::new(operation, target, e))?;
+
+    match run() {
+        Ok(()) => {
+            let _ = remove_tree(&aside, &JobProgress::default());
+            Ok(())
+        }
+        Err(e) => {
+            let _ = std::fs::rename(&aside, target);
+            Err(e)
+        }
+    }
+}
+
+// Copy to the other filesystem, check the copy and only then delete the original
+fn move_across_devices(source: &Path, target: &Path, progress: &JobProgress) -> FsResult<()> {
+    if std::fs::symlink_metadata(target).is_ok() {
+        return Err(already_exists(FsOperation::Move, target));
+    }
+
+    // The rename was counted as one item, now it's a copy and a delete of everything
+    let (items, bytes) = measure(source, progress);
+    progress
+        .items_total
+        .fetch_add((items * 2).saturating_sub(1), Ordering::Relaxed);
+    progress.bytes_total.fetch_add(bytes, Ordering::Relaxed);
+
+    let copied = copy_tree_with(source, target, progress, true).and_then(|()| {
+        verify_copy(source, target).map_err(|e| FsError::new(FsOperation::Move, target, e))
+    });
+    if let Err(e) = copied {
+        let _ = remove_tree(target, &JobProgress::default());
+        return Err(e);
+    }
+
+    remove_tree(source, progress)
+}
+
+// Check that a copy has the same entries, file sizes and link targets as the original
+fn verify_copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mismatch = |what: &str| {
+        Err(std::io::Error::other(format!(
+            "copy doesn't match the original ({} differs for '{}')",
+            what,
+            to.display()
+        )))
+    };
+
+    let from_meta = std::fs::symlink_metadata(from)?;
+    let to_meta = std::fs::symlink_metadata(to)?;
+    if from_meta.file_type() != to_meta.file_type() {
+        return mismatch("type");
+    }
+
+    if from_meta.file_type().is_symlink() {
+        if std::fs::read_link(from)? != std::fs::read_link(to)? {
+            return mismatch("link target");
+        }
+    } else if from_meta.is_dir() {
+        let mut count = 0;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            verify_copy(&entry.path(), &to.join(entry.file_name()))?;
+            count += 1;
+        }
+        if std::fs::read_dir(to)?.count() != count {
+            return mismatch("number of entries");
+        }
+    } else if from_meta.len() != to_meta.len() {
+        return mismatch("size");
+    }
+
+    Ok(())
+}
+
+// Number of items and bytes of file contents under a path, symlinks aren't followed.
+// Stops counting once the job is cancelled.
+fn measure(path: &Path, progress: &JobProgress) -> (u64, u64) {
+    if progress.is_cancelled() {
+        return (0, 0);
+    }
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return (1, 0);
+    };
+    if !meta.is_dir() {
+        return (1, if meta.is_file() { meta.len() } else { 0 });
+    }
+
+    let mut totals = (1, 0);
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let (items, bytes) = measure(&entry.path(), progress);
+            totals.0 += items;
+            totals.1 += bytes;
+        }
+    }
+    totals
+}
+
+// Copy a file or folder to the given path, recreating symlinks instead of following them.
+// Permissions and timestamps are kept.
+pub fn copy_tree(from: &Path, to: &Path, progress: &JobProgress) -> FsResult<()> {
+    copy_tree_with(from, to, progress, false)
+}
+
+// Moves also try to keep the owner, which only works for root or one's own files
+fn copy_tree_with(
+    from: &Path,
+    to: &Path,
+    progress: &JobProgress,
+    keep_owner: bool,
+) -> FsResult<()> {
+    let copy_err = |path: &Path| {
+        let path = path.to_path_buf();
+        move |e| FsError::new(FsOperation::Copy, &path, e)
+    };
+
+    progress.checkpoint().map_err(copy_err(from))?;
+    let meta = std::fs::symlink_metadata(from).map_err(copy_err(from))?;
+
+    if meta.file_type().is_symlink() {
+        #[cfg(unix)]
+        {
+            let link_target = std::fs::read_link(from).map_err(copy_err(from))?;
+            std::os::unix::fs::symlink(link_target, to).map_err(copy_err(to))?;
+        }
+        #[cfg(not(unix))]
+        {
+            std::fs::copy(from, to).map_err(copy_err(from))?;
+        }
+    } else if meta.is_dir() {
+        std::fs::create_dir(to).map_err(copy_err(to))?;
+        for entry in std::fs::read_dir(from).map_err(copy_err(from))? {
+            let entry = entry.map_err(copy_err(from))?;
+            copy_tree_with(
+                &entry.path(),
+                &to.join(entry.file_name()),
+                progress,
+                keep_owner,
+            )?;
+        }
+        // After the children, adding them changed the folder's times
+        if let Ok(dir) = std::fs::File::open(to) {
+            let _ = dir.set_times(file_times(&meta));
+        }
+        std::fs::set_permissions(to, meta.permissions()).map_err(copy_err(to))?;
+    } else {
+        let file = copy_file(from, to, progress).map_err(copy_err(from))?;
+        let _ = file.set_times(file_times(&meta));
+        std::fs::set_permissions(to, meta.permissions()).map_err(copy_err(to))?;
+    }
+
+    #[cfg(unix)]
+    if keep_owner {
+        use std::os::unix::fs::MetadataExt;
+        let _ = std::os::unix::fs::lchown(to, Some(meta.uid()), Some(meta.gid()));
+    }
+
+    progress.items_done.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+fn file_times(meta: &std::fs::Metadata) -> std::fs::FileTimes {
+    let mut times = std::fs::FileTimes::new();
+    if let Ok(accessed) = meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = meta.modified() {
+        times = times.set_modified(modified);
+    }
+    times
+}
+
+// Copy file contents in chunks so progress and pausing stay responsive on large files.
+// Never writes into an existing file. Returns the written file.
+fn copy_file(from: &Path, to: &Path, progress: &JobProgress) -> std::io::Result<std::fs::File> {
+    let mut reader = std::fs::File::open(from)?;
+    let mut writer = std::fs::File::create_new(to)?;
+    let mut buffer = vec![0; CHUNK_SIZE];
+
+    loop {
+        progress.checkpoint()?;
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        progress
+            .bytes_done
+            .fetch_add(read as u64, Ordering::Relaxed);
+    }
+
+    writer.flush()?;
+    Ok(writer)
+}
+
+// Delete a file or folder item by item, so a cancelled delete stops partway
+fn remove_tree(path: &Path, progress: &JobProgress) -> FsResult<()> {
+    let delete_err = |path: &Path| {
+        let path = path.to_path_buf();
+        move |e| FsError::new(FsOperation::Delete, &path, e)
+    };
+
+    progress.checkpoint().map_err(delete_err(path))?;
+    let meta = std::fs::symlink_metadata(path).map_err(delete_err(path))?;
+
+    if meta.is_dir() {
+        for entry in std::fs::read_dir(path).map_err(delete_err(path))? {
+            let entry = entry.map_err(delete_err(path))?;
+            remove_tree(&entry.path(), progress)?;
+        }
+        std::fs::remove_dir(path).map_err(delete_err(path))?;
+    } else {
+        std::fs::remove_file(path).map_err(delete_err(path))?;
+    }
+
+    progress.items_done.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
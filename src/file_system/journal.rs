@@ -0,0 +1,351 @@
+use super::error::{FsError, FsOperation, FsResult};
+use super::jobs::{self, JobKind, JobOrigin, JobProgress};
+use super::trash::{self, TrashEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const MAX_ENTRIES: usize = 100;
+
+// A single reversible change made through the app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Operation {
+    Move { from: PathBuf, to: PathBuf },
+    Rename { from: PathBuf, to: PathBuf },
+    Copy { from: PathBuf, to: PathBuf },
+    Create { path: PathBuf, is_dir: bool },
+    Trash { entry: TrashEntry },
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+impl Operation {
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Move { from, to } => format!(
+                "Moved '{}' to '{}'",
+                file_name(from),
+                to.parent().map(file_name).unwrap_or_default()
+            ),
+            Operation::Rename { from, to } => {
+                format!("Renamed '{}' to '{}'", file_name(from), file_name(to))
+            }
+            Operation::Copy { from, .. } => format!("Copied '{}'", file_name(from)),
+            Operation::Create { path, .. } => format!("Created '{}'", file_name(path)),
+            Operation::Trash { entry } => {
+                format!("Moved '{}' to the trash", file_name(&entry.original_path))
+            }
+        }
+    }
+
+    // Paths whose entries change when the operation is done or undone
+    pub fn touched_paths(&self) -> Vec<PathBuf> {
+        match self {
+            Operation::Move { from, to }
+            | Operation::Rename { from, to }
+            | Operation::Copy { from, to } => vec![from.clone(), to.clone()],
+            Operation::Create { path, .. } => vec![path.clone()],
+            Operation::Trash { entry } => vec![entry.original_path.clone()],
+        }
+    }
+
+    // The copy or move an undo or redo of this makes, too slow to do on the spot. Run as a job
+    // of the kind from the first path to the second.
+    fn transfer(&self, origin: JobOrigin) -> Option<(JobKind, PathBuf, PathBuf)> {
+        match (self, origin) {
+            (Operation::Move { from, to }, JobOrigin::Undo) => {
+                let to_dir = from.parent()?.to_path_buf();
+                Some((JobKind::Move { to_dir }, to.clone(), from.clone()))
+            }
+            (Operation::Move { from, to }, JobOrigin::Redo) => {
+                let to_dir = to.parent()?.to_path_buf();
+                Some((JobKind::Move { to_dir }, from.clone(), to.clone()))
+            }
+            (Operation::Copy { from, to }, JobOrigin::Redo) => {
+                let to_dir = to.parent()?.to_path_buf();
+                Some((JobKind::Copy { to_dir }, from.clone(), to.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    fn undo(&mut self) -> FsResult<()> {
+        match self {
+            Operation::Move { from, to } => rename_back(to, from, FsOperation::Move),
+            Operation::Rename { from, to } => rename_back(to, from, FsOperation::Rename),
+            // Copies and new entries may have been edited since, keep them recoverable
+            Operation::Copy { to: path, .. } | Operation::Create { path, .. } => {
+                trash::trash_path(path).map(|_| ())
+            }
+            Operation::Trash { entry } => trash::restore(entry).map(|_| ()),
+        }
+    }
+
+    fn redo(&mut self) -> FsResult<()> {
+        match self {
+            Operation::Move { from, to } => rename_back(from, to, FsOperation::Move),
+            Operation::Rename { from, to } => rename_back(from, to, FsOperation::Rename),
+            Operation::Copy { from, to } => {
+                ensure_free(to, FsOperation::Copy)?;
+                jobs::copy_tree(from, to, &JobProgress::default())
+            }
+            Operation::Create { path, is_dir } => {
+                let create_result = if *is_dir {
+                    std::fs::create_dir(&*path)
+                } else {
+                    std::fs::File::create_new(&*path).map(|_| ())
+                };
+                create_result.map_err(|e| FsError::new(FsOperation::Create, path, e))
+            }
+            Operation::Trash { entry } => {
+                *entry = trash::trash_path(&entry.original_path)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+// Undo and redo never replace something that appeared at the destination meanwhile
+fn ensure_free(path: &Path, operation: FsOperation) -> FsResult<()> {
+    if std::fs::symlink_metadata(path).is_ok() {
+        return Err(FsError::new(
+            operation,
+            path,
+            std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "an item with the same name already exists",
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn rename_back(from: &Path, to: &Path, operation: FsOperation) -> FsResult<()> {
+    ensure_free(to, operation)?;
+    std::fs::rename(from, to).map_err(|e| FsError::new(operation, from, e))
+}
+
+// Operations that are undone together, e.g. everything pasted at once
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub operations: Vec<Operation>,
+}
+
+impl JournalEntry {
+    pub fn describe(&self) -> String {
+        match self.operations.as_slice() {
+            [operation] => operation.describe(),
+            operations => format!("{} operations", operations.len()),
+        }
+    }
+
+    pub fn touched_paths(&self) -> Vec<PathBuf> {
+        self.operations
+            .iter()
+            .flat_map(Operation::touched_paths)
+            .collect()
+    }
+
+    // Whether undoing or redoing this can put items in the trash or take them out
+    pub fn touches_trash(&self) -> bool {
+        self.operations.iter().any(|operation| {
+            matches!(
+                operation,
+                Operation::Copy { .. } | Operation::Create { .. } | Operation::Trash { .. }
+            )
+        })
+    }
+
+    // The job that undoes or redoes this when every operation of it is a transfer
+    fn transfer(&self, origin: JobOrigin) -> Option<Step> {
+        let mut kind = None;
+        let mut pairs = Vec::new();
+        for operation in &self.operations {
+            let (operation_kind, from, to) = operation.transfer(origin)?;
+            kind.get_or_insert(operation_kind);
+            pairs.push((from, to));
+        }
+        Some(Step::Transfer { kind: kind?, pairs })
+    }
+}
+
+// How an undo or redo step is carried out
+pub enum Step {
+    // Done on the spot
+    Done(FsResult<JournalEntry>),
+    // Copies or moves data, run as a job that reports back through finish_transfer
+    Transfer {
+        kind: JobKind,
+        pairs: Vec<(PathBuf, PathBuf)>,
+    },
+}
+
+// Undo and redo stacks, saved to the runtime dir so they survive restarts within a login session
+#[derive(Default, Serialize, Deserialize)]
+pub struct Journal {
+    undo: Vec<JournalEntry>,
+    redo: Vec<JournalEntry>,
+    #[serde(skip)]
+    batch: Option<Vec<Operation>>,
+    // Step whose job is running, taken off its stack until the job finishes
+    #[serde(skip)]
+    running: Option<(JobOrigin, JournalEntry)>,
+}
+
+fn journal_path() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("rustplorer")
+        .join("journal.json")
+}
+
+impl Journal {
+    pub fn load() -> Self {
+        std::fs::read_to_string(journal_path())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    // Write the stacks out, after every change the app makes to them
+    pub fn save(&self) -> FsResult<()> {
+        let path = journal_path();
+        serde_json::to_string(self)
+            .map_err(std::io::Error::other)
+            .and_then(|contents| {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let tmp_path = path.with_extension("json.tmp");
+                std::fs::write(&tmp_path, contents)?;
+                std::fs::rename(&tmp_path, &path)
+            })
+            .map_err(|e| FsError::new(FsOperation::SaveJournal, &path, e))
+    }
+
+    // Group everything recorded until end_batch into one undo step
+    pub fn begin_batch(&mut self) {
+        self.batch.get_or_insert_with(Vec::new);
+    }
+
+    pub fn end_batch(&mut self) -> Option<&JournalEntry> {
+        let operations = self.batch.take()?;
+        if operations.is_empty() {
+            return None;
+        }
+        self.push(JournalEntry { operations })
+    }
+
+    // Returns the new undo step, or None while a batch is open
+    pub fn record(&mut self, operation: Operation) -> Option<&JournalEntry> {
+        if let Some(batch) = &mut self.batch {
+            batch.push(operation);
+            return None;
+        }
+        self.push(JournalEntry {
+            operations: vec![operation],
+        })
+    }
+
+    fn push(&mut self, entry: JournalEntry) -> Option<&JournalEntry> {
+        self.undo.push(entry);
+        if self.undo.len() > MAX_ENTRIES {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.undo.last()
+    }
+
+    // Revert the last step. When one of its operations fails, the ones already reverted can
+    // be redone and the rest, the failed one included, stay to be undone again.
+    // Nothing happens while the job of a step is running.
+    pub fn undo(&mut self) -> Option<Step> {
+        if self.running.is_some() {
+            return None;
+        }
+        let mut entry = self.undo.pop()?;
+        if let Some(step) = entry.transfer(JobOrigin::Undo) {
+            self.running = Some((JobOrigin::Undo, entry));
+            return Some(step);
+        }
+
+        let mut remaining = entry.operations.len();
+        while remaining > 0 {
+            if let Err(e) = entry.operations[remaining - 1].undo() {
+                let reverted = entry.operations.split_off(remaining);
+                if !reverted.is_empty() {
+                    self.redo.push(JournalEntry {
+                        operations: reverted,
+                    });
+                }
+                self.undo.push(entry);
+                return Some(Step::Done(Err(e)));
+            }
+            remaining -= 1;
+        }
+
+        self.redo.push(entry.clone());
+        Some(Step::Done(Ok(entry)))
+    }
+
+    // Same as undo the other way round
+    pub fn redo(&mut self) -> Option<Step> {
+        if self.running.is_some() {
+            return None;
+        }
+        let mut entry = self.redo.pop()?;
+        if let Some(step) = entry.transfer(JobOrigin::Redo) {
+            self.running = Some((JobOrigin::Redo, entry));
+            return Some(step);
+        }
+
+        for done in 0..entry.operations.len() {
+            if let Err(e) = entry.operations[done].redo() {
+                let redone: Vec<Operation> = entry.operations.drain(..done).collect();
+                if !redone.is_empty() {
+                    self.undo.push(JournalEntry { operations: redone });
+                }
+                self.redo.push(entry);
+                return Some(Step::Done(Err(e)));
+            }
+        }
+
+        self.undo.push(entry.clone());
+        Some(Step::Done(Ok(entry)))
+    }
+
+    // Settle the running step once its job finished with the given (source, target) pairs
+    // done. Those operations move to the other stack, the ones that failed or were cancelled
+    // stay to be tried again. Returns the part that went through.
+    pub fn finish_transfer(&mut self, done: &[(PathBuf, PathBuf)]) -> Option<JournalEntry> {
+        let (origin, entry) = self.running.take()?;
+        let done: HashSet<&(PathBuf, PathBuf)> = done.iter().collect();
+        let (finished, left): (Vec<Operation>, Vec<Operation>) =
+            entry.operations.into_iter().partition(|operation| {
+                operation
+                    .transfer(origin)
+                    .is_some_and(|(_, from, to)| done.contains(&(from, to)))
+            });
+
+        let (forward, back) = match origin {
+            JobOrigin::Undo => (&mut self.redo, &mut self.undo),
+            _ => (&mut self.undo, &mut self.redo),
+        };
+        if !left.is_empty() {
+            back.push(JournalEntry { operations: left });
+        }
+        if finished.is_empty() {
+            return None;
+        }
+        let entry = JournalEntry {
+            operations: finished,
+        };
+        forward.push(entry.clone());
+        Some(entry)
+    }
+}
@@ -0,0 +1,236 @@
+use super::error::{FsError, FsOperation};
+use super::file_tree::{self, FileNode};
+use crate::utils::sorting::Collation;
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const WORKER_COUNT: usize = 4;
+const BATCH_SIZE: usize = 256;
+const FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
+struct Job {
+    id: u64,
+    tab: u64,
+    path: PathBuf,
+    count_items: bool,
+    cancelled: Arc<AtomicBool>,
+}
+
+enum LoadEvent {
+    Entries(Vec<FileNode>),
+    Finished,
+    Failed(std::io::Error),
+}
+
+struct Message {
+    id: u64,
+    tab: u64,
+    path: PathBuf,
+    event: LoadEvent,
+}
+
+// Lists directories on a pool of worker threads and streams the entries back. One loader
+// serves every tab, listings are kept apart by the id of the tab that asked for them.
+pub struct DirLoader {
+    job_tx: Sender<Job>,
+    event_rx: Receiver<Message>,
+    active: HashMap<(u64, PathBuf), (u64, Arc<AtomicBool>)>,
+    next_id: u64,
+    // Whether listed folders get their entries counted too
+    count_items: bool,
+}
+
+impl DirLoader {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (job_tx, job_rx) = mpsc::channel::<Job>();
+        let (event_tx, event_rx) = mpsc::channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        for i in 0..WORKER_COUNT {
+            let job_rx = Arc::clone(&job_rx);
+            let event_tx = event_tx.clone();
+            let ctx = ctx.clone();
+
+            std::thread::Builder::new()
+                .name(format!("dir-loader-{i}"))
+                .spawn(move || {
+                    loop {
+                        // Release the lock before reading so other workers can pick up jobs
+                        let job = {
+                            let rx = job_rx.lock().unwrap();
+                            rx.recv()
+                        };
+                        match job {
+                            Ok(job) => read_dir_job(&job, &event_tx, &ctx),
+                            Err(_) => break,
+                        }
+                    }
+                })
+                .expect("Couldn't spawn directory loader thread.");
+        }
+
+        Self {
+            job_tx,
+            event_rx,
+            active: HashMap::new(),
+            next_id: 0,
+            count_items: false,
+        }
+    }
+
+    // Start loading the children of a tab's node, replacing any pending load of the same path
+    pub fn load(&mut self, tab: u64, node: &mut FileNode) {
+        if !node.is_dir {
+            return;
+        }
+
+        self.cancel(tab, &node.path);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.active
+            .insert((tab, node.path.clone()), (id, Arc::clone(&cancelled)));
+
+        node.children = Some(Vec::new());
+        node.loading = true;
+        node.load_error = None;
+
+        let _ = self.job_tx.send(Job {
+            id,
+            tab,
+            path: node.path.clone(),
+            count_items: self.count_items,
+            cancelled,
+        });
+    }
+
+    // Count the entries of listed folders, which takes a while on large or slow ones.
+    // Returns whether counting was just turned on, listings done before lack the counts.
+    pub fn set_count_items(&mut self, count_items: bool) -> bool {
+        let turned_on = count_items && !self.count_items;
+        self.count_items = count_items;
+        turned_on
+    }
+
+    // Stop a pending load, its remaining entries are dropped
+    pub fn cancel(&mut self, tab: u64, path: &Path) {
+        if let Some((_, cancelled)) = self.active.remove(&(tab, path.to_path_buf())) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Stop every pending load of a closed tab
+    pub fn cancel_tab(&mut self, tab: u64) {
+        self.active.retain(|(owner, _), (_, cancelled)| {
+            if *owner == tab {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+            *owner != tab
+        });
+    }
+
+    // Apply entries that arrived since the last frame to the trees of the tabs, given with
+    // their ids. Returns failed listings.
+    pub fn poll<'a>(
+        &mut self,
+        trees: impl IntoIterator<Item = (u64, &'a mut FileNode)>,
+        collation: Collation,
+    ) -> Vec<FsError> {
+        let mut trees: HashMap<u64, &mut FileNode> = trees.into_iter().collect();
+        let mut errors = Vec::new();
+
+        while let Ok(message) = self.event_rx.try_recv() {
+            let key = (message.tab, message.path);
+            // Ignore results from cancelled or superseded jobs
+            let is_current = self
+                .active
+                .get(&key)
+                .is_some_and(|(id, _)| *id == message.id);
+            if !is_current {
+                continue;
+            }
+
+            let is_done = !matches!(message.event, LoadEvent::Entries(_));
+            if is_done {
+                self.active.remove(&key);
+            }
+
+            let (tab, path) = key;
+            let Some(node) = trees.get_mut(&tab).and_then(|root| root.find_mut(&path)) else {
+                continue;
+            };
+
+            match message.event {
+                LoadEvent::Entries(entries) => {
+                    node.children.get_or_insert_with(Vec::new).extend(entries);
+                }
+                LoadEvent::Finished => {
+                    let children = node.children.take().unwrap_or_default();
+                    node.children = Some(file_tree::sort_children(children, collation));
+                    node.loading = false;
+                }
+                LoadEvent::Failed(e) => {
+                    node.loading = false;
+                    node.load_error = Some(e.to_string());
+                    errors.push(FsError::new(FsOperation::ReadDir, &path, e));
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+fn read_dir_job(job: &Job, events: &Sender<Message>, ctx: &egui::Context) {
+    let send = |event: LoadEvent| {
+        let _ = events.send(Message {
+            id: job.id,
+            tab: job.tab,
+            path: job.path.clone(),
+            event,
+        });
+        ctx.request_repaint();
+    };
+
+    let entries = match std::fs::read_dir(&job.path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            send(LoadEvent::Failed(e));
+            return;
+        }
+    };
+
+    let mut batch = Vec::new();
+    let mut last_flush = Instant::now();
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if job.cancelled.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut node = FileNode::new(&entry.path());
+        if job.count_items && node.is_dir {
+            node.metadata.item_count = std::fs::read_dir(&node.path)
+                .ok()
+                .map(|entries| entries.count() as u64);
+        }
+        batch.push(node);
+
+        // Flush often enough that slow disks still show progress
+        if batch.len() >= BATCH_SIZE || last_flush.elapsed() >= FLUSH_INTERVAL {
+            send(LoadEvent::Entries(std::mem::take(&mut batch)));
+            last_flush = Instant::now();
+        }
+    }
+
+    if !batch.is_empty() {
+        send(LoadEvent::Entries(batch));
+    }
+    send(LoadEvent::Finished);
+}
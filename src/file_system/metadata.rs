@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    #[default]
+    Other,
+}
+
+// Snapshot of a file's metadata, taken once when the node is created.
+// Every field is optional since not all platforms and filesystems provide them.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Default)]
+pub struct FileMetadata {
+    pub kind: FileKind,
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    pub changed: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    // Entries of a folder, only counted by the loader while the Items column is shown
+    pub item_count: Option<u64>,
+    pub permissions: Option<u32>,
+    pub readonly: bool,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub symlink_target: Option<PathBuf>,
+}
+
+impl FileMetadata {
+    pub fn read(path: &Path) -> Self {
+        let Ok(link_meta) = std::fs::symlink_metadata(path) else {
+            return Self::default();
+        };
+
+        let is_symlink = link_meta.file_type().is_symlink();
+        let symlink_target = if is_symlink {
+            std::fs::read_link(path).ok()
+        } else {
+            None
+        };
+
+        // Describe what a link points to, unless it's broken
+        let meta = if is_symlink {
+            std::fs::metadata(path).unwrap_or(link_meta)
+        } else {
+            link_meta
+        };
+
+        let kind = if is_symlink {
+            FileKind::Symlink
+        } else if meta.is_dir() {
+            FileKind::Dir
+        } else if meta.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+
+        #[cfg(unix)]
+        let (changed, permissions, owner, group) = {
+            use std::os::unix::fs::MetadataExt;
+
+            let changed = u64::try_from(meta.ctime()).ok().map(|secs| {
+                SystemTime::UNIX_EPOCH
+                    + std::time::Duration::new(secs, meta.ctime_nsec().clamp(0, 999_999_999) as u32)
+            });
+            (
+                changed,
+                Some(meta.mode()),
+                users::user_name(meta.uid()),
+                users::group_name(meta.gid()),
+            )
+        };
+        #[cfg(not(unix))]
+        let (changed, permissions, owner, group) = (None, None, None, None);
+
+        Self {
+            kind,
+            size: meta.is_file().then_some(meta.len()),
+            modified: meta.modified().ok(),
+            changed,
+            created: meta.created().ok(),
+            accessed: meta.accessed().ok(),
+            item_count: None,
+            permissions,
+            readonly: meta.permissions().readonly(),
+            owner,
+            group,
+            symlink_target,
+        }
+    }
+}
+
+#[cfg(unix)]
+mod users {
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+
+    // Parse an /etc/passwd style file into an id -> name table
+    fn read_table(path: &str) -> HashMap<u32, String> {
+        std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                let id = fields.nth(1)?.parse().ok()?;
+                Some((id, name.to_string()))
+            })
+            .collect()
+    }
+
+    pub fn user_name(uid: u32) -> Option<String> {
+        static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+        USERS
+            .get_or_init(|| read_table("/etc/passwd"))
+            .get(&uid)
+            .cloned()
+            .or_else(|| Some(uid.to_string()))
+    }
+
+    pub fn group_name(gid: u32) -> Option<String> {
+        static GROUPS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+        GROUPS
+            .get_or_init(|| read_table("/etc/group"))
+            .get(&gid)
+            .cloned()
+            .or_else(|| Some(gid.to_string()))
+    }
+}
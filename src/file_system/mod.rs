@@ -1,3 +1,10 @@
+pub mod error;
 pub mod file_tree;
 pub mod formatting;
+pub mod jobs;
+pub mod journal;
+pub mod loader;
+pub mod metadata;
 pub mod operations;
+pub mod trash;
+pub mod watcher;
@@ -1,136 +1,425 @@
+use super::error::{FsError, FsOperation, FsResult};
 use super::file_tree::FileNode;
+use super::trash::TrashEntry;
 use eframe::egui;
+use std::path::{Path, PathBuf};
+
+// A filesystem change requested by the views, carried out by the app
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileAction {
+    Reload(PathBuf),
+    Delete(Vec<PathBuf>),
+    Move {
+        paths: Vec<PathBuf>,
+        to_dir: PathBuf,
+    },
+    Copy {
+        paths: Vec<PathBuf>,
+        to_dir: PathBuf,
+    },
+    SetClipboard(Clipboard),
+    Paste {
+        to_dir: PathBuf,
+    },
+    StartRename(PathBuf),
+    Rename {
+        from: PathBuf,
+        new_name: String,
+    },
+    NewFolder(PathBuf),
+    NewFile(PathBuf),
+    Trash(Vec<PathBuf>),
+    RestoreFromTrash(TrashEntry),
+    DeleteFromTrash(TrashEntry),
+    EmptyTrash,
+    Undo,
+    Redo,
+    AddToFavorites(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClipboardMode {
+    Copy,
+    Cut,
+}
+
+// Files waiting to be pasted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clipboard {
+    pub paths: Vec<PathBuf>,
+    pub mode: ClipboardMode,
+}
+
+impl Clipboard {
+    pub fn is_cut(&self, path: &Path) -> bool {
+        self.mode == ClipboardMode::Cut && self.paths.iter().any(|p| p == path)
+    }
+}
+
+// Paths to delete, how to name them and whether a non-empty folder is among them
+type DeleteConfirmation = (Vec<PathBuf>, String, bool);
 
 // Handle delete confirmation modal
 pub fn show_delete_confirmation_modal(
     ui: &mut egui::Ui,
     confirm_delete_id: egui::Id,
-) -> Option<usize> {
-    let mut delete_idx = None;
+) -> Option<Vec<PathBuf>> {
+    let mut delete_paths = None;
 
-    if let Some((_, idx, name, _)) =
-        ui.data_mut(|d| d.get_temp::<(bool, usize, String, bool)>(confirm_delete_id))
+    if let Some((paths, description, has_children)) =
+        ui.data_mut(|d| d.get_temp::<DeleteConfirmation>(confirm_delete_id))
     {
         egui::Window::new("Confirm Delete")
             .collapsible(false)
             .resizable(false)
             .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
             .show(ui.ctx(), |ui| {
-                ui.label(format!("Are you sure you want to delete '{}'?", name));
-                ui.label("This will delete all contents permanently.");
+                ui.label(format!(
+                    "Are you sure you want to permanently delete {}?",
+                    description
+                ));
+                if has_children {
+                    ui.label("This will delete all contents permanently.");
+                } else {
+                    ui.label("It won't be moved to the trash and can't be restored.");
+                }
 
                 ui.horizontal(|ui| {
                     if ui.button("Cancel").clicked() {
-                        ui.data_mut(|d| d.remove::<(bool, usize, String, bool)>(confirm_delete_id));
+                        ui.data_mut(|d| d.remove::<DeleteConfirmation>(confirm_delete_id));
                     }
                     if ui.button("Delete").clicked() {
-                        delete_idx = Some(idx);
-                        ui.data_mut(|d| d.remove::<(bool, usize, String, bool)>(confirm_delete_id));
+                        delete_paths = Some(paths.clone());
+                        ui.data_mut(|d| d.remove::<DeleteConfirmation>(confirm_delete_id));
                     }
                 });
             });
     }
 
-    delete_idx
+    delete_paths
+}
+
+// Where moving a file or folder into another folder puts it
+pub fn move_target(from: &Path, to_dir: &Path) -> FsResult<PathBuf> {
+    let Some(name) = from.file_name() else {
+        return Err(FsError::new(
+            FsOperation::Move,
+            from,
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name"),
+        ));
+    };
+
+    if to_dir.starts_with(from) {
+        return Err(FsError::new(
+            FsOperation::Move,
+            from,
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "can't move a folder into itself",
+            ),
+        ));
+    }
+
+    Ok(to_dir.join(name))
+}
+
+// Where copying a file or folder into another folder puts it.
+// Copying next to the original picks a free name instead of overwriting it.
+pub fn copy_target(from: &Path, to_dir: &Path) -> FsResult<PathBuf> {
+    let Some(name) = from.file_name() else {
+        return Err(FsError::new(
+            FsOperation::Copy,
+            from,
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name"),
+        ));
+    };
+
+    if to_dir.starts_with(from) {
+        return Err(FsError::new(
+            FsOperation::Copy,
+            from,
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "can't copy a folder into itself",
+            ),
+        ));
+    }
+
+    if from.parent() == Some(to_dir) {
+        return Ok(unique_path(to_dir, &name.to_string_lossy()));
+    }
+    Ok(to_dir.join(name))
+}
+
+// Check a new name for an entry before renaming it
+pub fn validate_name(path: &Path, new_name: &str) -> Result<(), String> {
+    if new_name.trim().is_empty() {
+        return Err("Name can't be empty".to_string());
+    }
+    if new_name == "." || new_name == ".." {
+        return Err(format!("'{}' is not a valid name", new_name));
+    }
+    if new_name.contains('/') {
+        return Err("Name can't contain '/'".to_string());
+    }
+    if new_name.contains('\0') {
+        return Err("Name can't contain a null character".to_string());
+    }
+    #[cfg(target_os = "windows")]
+    if let Some(c) = new_name
+        .chars()
+        .find(|c| matches!(c, '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*'))
+    {
+        return Err(format!("Name can't contain '{}'", c));
+    }
+    if new_name.len() > 255 {
+        return Err("Name is too long".to_string());
+    }
+
+    // Renaming onto the same file, e.g. a case change on Windows, is fine
+    let target = path.with_file_name(new_name);
+    if target != path
+        && std::fs::symlink_metadata(&target).is_ok()
+        && std::fs::canonicalize(&target).ok() != std::fs::canonicalize(path).ok()
+    {
+        return Err(format!("'{}' already exists", new_name));
+    }
+
+    Ok(())
 }
 
-// Execute a file/folder deletion
-pub fn execute_delete(node: &mut FileNode, idx: usize) {
-    // Ensure children are loaded so we can safely index
-    node.ensure_children_loaded();
-    let children = node.children.as_mut().unwrap();
+// Rename a file or folder within its folder, returns its new path
+pub fn rename_path(from: &Path, new_name: &str) -> FsResult<PathBuf> {
+    validate_name(from, new_name).map_err(|message| {
+        FsError::new(
+            FsOperation::Rename,
+            from,
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, message),
+        )
+    })?;
+
+    let target_path = from.with_file_name(new_name);
+    std::fs::rename(from, &target_path).map_err(|e| FsError::new(FsOperation::Rename, from, e))?;
+
+    Ok(target_path)
+}
 
-    let child = &children[idx];
+// Create an empty folder or file with a free default name, returns its path
+pub fn create_entry(dir: &Path, is_dir: bool) -> FsResult<PathBuf> {
+    let name = if is_dir { "New Folder" } else { "New File" };
+    let path = unique_path(dir, name);
 
-    let delete_result = if child.is_dir {
-        std::fs::remove_dir_all(&child.path)
+    let create_result = if is_dir {
+        std::fs::create_dir(&path)
     } else {
-        std::fs::remove_file(&child.path)
+        std::fs::File::create_new(&path).map(|_| ())
     };
 
-    match delete_result {
-        Ok(_) => {
-            children.remove(idx);
-        }
-        Err(e) => {
-            eprintln!("Failed to delete {}: {}", child.path.display(), e);
-        }
+    create_result
+        .map(|()| path)
+        .map_err(|e| FsError::new(FsOperation::Create, dir, e))
+}
+
+// First free "name", "name (2)", "name (3)"... in a folder, keeping the extension last
+pub fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if std::fs::symlink_metadata(&candidate).is_err() {
+        return candidate;
     }
+
+    let (stem, extension) = match name.rfind('.') {
+        Some(idx) if idx > 0 => name.split_at(idx),
+        _ => (name, ""),
+    };
+
+    (2..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| std::fs::symlink_metadata(path).is_err())
+        .unwrap()
 }
 
-// Execute a file/folder move operation
-pub fn execute_move(
-    node: &mut FileNode,
-    from_idx: usize,
-    target_folder_idx: usize,
-    original_children: Vec<FileNode>,
-) {
-    let moved_item = original_children[from_idx].clone();
-    let target_folder = original_children[target_folder_idx].clone();
-    let target_path = target_folder.path.join(&moved_item.name);
-
-    match std::fs::rename(&moved_item.path, &target_path) {
-        Ok(_) => {
-            // Rebuild current children list (from original) and remove moved item
-            node.ensure_children_loaded();
-            let children = node.children.as_mut().unwrap();
-
-            *children = original_children;
-            children.retain(|child| child.path != moved_item.path);
-
-            // Refresh the target folder (if it's one of our children)
-            if let Some(target) = children.iter_mut().find(|c| c.path == target_folder.path) {
-                target.children = None; // mark as needing reload
-                target.ensure_children_loaded(); // reload its contents
-            }
-
-            println!("Moved {} into {}", moved_item.name, target_folder.name);
-        }
-        Err(e) => {
-            // Restore original state on failure
-            node.ensure_children_loaded();
-            *node.children.as_mut().unwrap() = original_children;
-            eprintln!("Failed to move file: {}", e);
-        }
-    }
-}
-
-//TODO: Implement renaming, copying, (not file clicked) pasting, refreshing, etc.
-// Handle context menu for delete
+// Put paths on the internal clipboard. The system clipboard gets them as text too,
+// which also makes Ctrl+V reach us since egui only reports pastes of non-empty text.
+pub fn set_clipboard(ui: &egui::Ui, paths: Vec<PathBuf>, mode: ClipboardMode) -> FileAction {
+    let text = paths
+        .iter()
+        .map(|path| path.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("\n");
+    ui.ctx().copy_text(text);
+
+    FileAction::SetClipboard(Clipboard { paths, mode })
+}
+
+// Ctrl+C / Ctrl+X on the selected items and Ctrl+V into the shown folder
+pub fn handle_clipboard_shortcuts(
+    ui: &egui::Ui,
+    selected: &[PathBuf],
+    current_dir: &Path,
+) -> Option<FileAction> {
+    if ui.ctx().wants_keyboard_input() {
+        return None;
+    }
+
+    let (copy, cut, paste) = ui.input(|i| {
+        i.events
+            .iter()
+            .fold((false, false, false), |acc, event| match event {
+                egui::Event::Copy => (true, acc.1, acc.2),
+                egui::Event::Cut => (acc.0, true, acc.2),
+                egui::Event::Paste(_) => (acc.0, acc.1, true),
+                _ => acc,
+            })
+    });
+
+    if !selected.is_empty() && copy {
+        Some(set_clipboard(ui, selected.to_vec(), ClipboardMode::Copy))
+    } else if !selected.is_empty() && cut {
+        Some(set_clipboard(ui, selected.to_vec(), ClipboardMode::Cut))
+    } else if paste {
+        Some(FileAction::Paste {
+            to_dir: current_dir.to_path_buf(),
+        })
+    } else {
+        None
+    }
+}
+
+// Handle context menu for the selected items, the right-clicked one among them
 pub fn show_context_menu(
     ui: &mut egui::Ui,
-    child: &FileNode,
-    child_idx: usize,
+    targets: &[&FileNode],
     confirm_delete_id: egui::Id,
-) -> Option<usize> {
-    let mut delete_request = None;
-
-    if ui.button("Delete").clicked() {
-        // For directories, check if we know the child count.
-        // We'll assume non-empty if its a dir and we haven't loaded children,
-        // or if children exist.
-        let is_non_empty_dir = child.is_dir
+) -> Option<FileAction> {
+    let mut action = None;
+    let paths = || targets.iter().map(|t| t.path.clone()).collect::<Vec<_>>();
+
+    if ui.button("Copy").clicked() {
+        action = Some(set_clipboard(ui, paths(), ClipboardMode::Copy));
+        ui.close();
+    }
+    if ui.button("Cut").clicked() {
+        action = Some(set_clipboard(ui, paths(), ClipboardMode::Cut));
+        ui.close();
+    }
+    if let [folder] = targets
+        && folder.is_dir
+        && ui.button("Paste into folder").clicked()
+    {
+        action = Some(FileAction::Paste {
+            to_dir: folder.path.clone(),
+        });
+        ui.close();
+    }
+    if let [folder] = targets
+        && folder.is_dir
+        && ui.button("Add to Favorites").clicked()
+    {
+        action = Some(FileAction::AddToFavorites(folder.path.clone()));
+        ui.close();
+    }
+
+    ui.separator();
+
+    if ui
+        .add_enabled(targets.len() == 1, egui::Button::new("Rename"))
+        .clicked()
+    {
+        action = Some(FileAction::StartRename(targets[0].path.clone()));
+        ui.close();
+    }
+    if ui.button("Move to Trash").clicked() {
+        action = Some(FileAction::Trash(paths()));
+        ui.close();
+    }
+    if ui.button("Delete Permanently").clicked() {
+        request_delete_confirmation(ui, targets, confirm_delete_id);
+        ui.close();
+    }
+
+    action
+}
+
+// Permanent deletes always go through the confirmation modal
+pub fn request_delete_confirmation(
+    ui: &egui::Ui,
+    targets: &[&FileNode],
+    confirm_delete_id: egui::Id,
+) {
+    // For directories, check if we know the child count.
+    // We'll assume non-empty if its a dir and we haven't loaded children,
+    // or if children exist.
+    let has_non_empty_dir = targets.iter().any(|child| {
+        child.is_dir
             && (child.children.is_none() || // not loaded - assume may have content
-                child.children.as_ref().map_or(false, |c| !c.is_empty()));
-
-        if is_non_empty_dir {
-            // Store confirmation data: (is_dir, index, name, has_children)
-            ui.data_mut(|d| {
-                d.insert_temp(
-                    confirm_delete_id,
-                    (
-                        true, // is confirmation needed
-                        child_idx,
-                        child.name.clone(),
-                        is_non_empty_dir, // or just true
-                    ),
-                )
-            });
-        } else {
-            delete_request = Some(child_idx);
-        }
+                child.children.as_ref().is_some_and(|c| !c.is_empty()))
+    });
+
+    let description = match targets {
+        [child] => format!("'{}'", child.name),
+        targets => format!("these {} items", targets.len()),
+    };
+    let paths = targets.iter().map(|t| t.path.clone()).collect::<Vec<_>>();
+
+    ui.data_mut(|d| {
+        d.insert_temp::<DeleteConfirmation>(
+            confirm_delete_id,
+            (paths, description, has_non_empty_dir),
+        )
+    });
+}
+
+// Delete moves the selected items to the trash, Shift+Delete asks to delete them for good
+pub fn handle_delete_shortcuts(
+    ui: &egui::Ui,
+    selected: &[&FileNode],
+    confirm_delete_id: egui::Id,
+) -> Option<FileAction> {
+    if selected.is_empty()
+        || ui.ctx().wants_keyboard_input()
+        || !ui.input(|i| i.key_pressed(egui::Key::Delete))
+    {
+        return None;
+    }
+
+    if ui.input(|i| i.modifiers.shift) {
+        request_delete_confirmation(ui, selected, confirm_delete_id);
+        None
+    } else {
+        Some(FileAction::Trash(
+            selected.iter().map(|t| t.path.clone()).collect(),
+        ))
+    }
+}
+
+// Handle context menu for the empty area of a view
+pub fn show_background_context_menu(
+    ui: &mut egui::Ui,
+    node: &FileNode,
+    clipboard: Option<&Clipboard>,
+) -> Option<FileAction> {
+    let mut action = None;
+
+    if ui.button("New Folder").clicked() {
+        action = Some(FileAction::NewFolder(node.path.clone()));
+        ui.close();
+    }
+    if ui.button("New File").clicked() {
+        action = Some(FileAction::NewFile(node.path.clone()));
+        ui.close();
+    }
+
+    ui.separator();
+
+    if ui
+        .add_enabled(clipboard.is_some(), egui::Button::new("Paste"))
+        .clicked()
+    {
+        action = Some(FileAction::Paste {
+            to_dir: node.path.clone(),
+        });
         ui.close();
     }
 
-    delete_request
+    action
 }
@@ -0,0 +1,425 @@
+// Freedesktop.org trash, see https://specifications.freedesktop.org/trash-spec/latest/
+use super::error::{FsError, FsOperation, FsResult};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+// An item sitting in one of the trash directories
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub trash_dir: PathBuf,
+    // Name under trash_dir/files, also the stem of its .trashinfo file
+    pub name: String,
+    pub original_path: PathBuf,
+    pub deletion_date: Option<String>,
+    pub is_dir: bool,
+}
+
+impl TrashEntry {
+    pub fn files_path(&self) -> PathBuf {
+        self.trash_dir.join("files").join(&self.name)
+    }
+
+    fn info_path(&self) -> PathBuf {
+        self.trash_dir
+            .join("info")
+            .join(format!("{}.trashinfo", self.name))
+    }
+}
+
+fn home_trash() -> PathBuf {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| {
+            PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".local/share")
+        });
+    data_home.join("Trash")
+}
+
+#[cfg(unix)]
+fn current_uid() -> std::io::Result<u32> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata("/proc/self").map(|meta| meta.uid())
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> std::io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::symlink_metadata(path).map(|meta| meta.dev())
+}
+
+// The top directory of the mount a path lives on
+#[cfg(unix)]
+fn mount_top_dir(path: &Path) -> std::io::Result<PathBuf> {
+    let dev = device_of(path)?;
+    let mut top = path.to_path_buf();
+    while let Some(parent) = top.parent() {
+        if device_of(parent)? != dev {
+            break;
+        }
+        top = parent.to_path_buf();
+    }
+    Ok(top)
+}
+
+// $topdir/.Trash/$uid if the admin set up a shared trash, $topdir/.Trash-$uid otherwise
+#[cfg(unix)]
+fn top_dir_trash(top_dir: &Path, uid: u32, create: bool) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let shared = top_dir.join(".Trash");
+    if let Ok(meta) = std::fs::symlink_metadata(&shared) {
+        let is_sticky = meta.permissions().mode() & 0o1000 != 0;
+        if meta.is_dir() && is_sticky {
+            let trash = shared.join(uid.to_string());
+            if trash.is_dir() || (create && std::fs::create_dir(&trash).is_ok()) {
+                return Some(trash);
+            }
+        }
+    }
+
+    let trash = top_dir.join(format!(".Trash-{}", uid));
+    if trash.is_dir() || (create && std::fs::create_dir(&trash).is_ok()) {
+        return Some(trash);
+    }
+    None
+}
+
+// The mount top directory a trash belongs to, None for the home trash
+fn top_dir_of(trash_dir: &Path) -> Option<PathBuf> {
+    if trash_dir == home_trash() {
+        return None;
+    }
+
+    let parent = trash_dir.parent()?;
+    if parent.ends_with(".Trash") {
+        parent.parent().map(Path::to_path_buf)
+    } else {
+        Some(parent.to_path_buf())
+    }
+}
+
+// Pick the trash directory for a path: the home trash if it's on the same mount
+#[cfg(unix)]
+fn trash_dir_for(path: &Path) -> std::io::Result<PathBuf> {
+    let home_trash = home_trash();
+    std::fs::create_dir_all(&home_trash)?;
+
+    if device_of(path)? == device_of(&home_trash)? {
+        return Ok(home_trash);
+    }
+
+    let top_dir = mount_top_dir(path)?;
+    top_dir_trash(&top_dir, current_uid()?, true).ok_or_else(|| {
+        Error::new(
+            ErrorKind::Unsupported,
+            format!("no usable trash on the drive of {}", top_dir.display()),
+        )
+    })
+}
+
+#[cfg(not(unix))]
+fn trash_dir_for(_path: &Path) -> std::io::Result<PathBuf> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "trash isn't supported on this platform",
+    ))
+}
+
+// Move a file or folder to the trash, returns where it ended up
+pub fn trash_path(path: &Path) -> FsResult<TrashEntry> {
+    let trash_err = |e| FsError::new(FsOperation::Trash, path, e);
+
+    let path = std::path::absolute(path).map_err(trash_err)?;
+    let is_dir = std::fs::symlink_metadata(&path)
+        .map(|meta| meta.is_dir())
+        .map_err(trash_err)?;
+    let trash_dir = trash_dir_for(&path).map_err(trash_err)?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    std::fs::create_dir_all(&files_dir).map_err(trash_err)?;
+    std::fs::create_dir_all(&info_dir).map_err(trash_err)?;
+
+    // Paths are stored relative to the top directory for trashes on other drives
+    let stored_path = match top_dir_of(&trash_dir) {
+        Some(top_dir) => path.strip_prefix(&top_dir).unwrap_or(&path).to_path_buf(),
+        None => path.clone(),
+    };
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unnamed".to_string());
+
+    // Claim a name by creating its info file first, as the spec asks
+    let mut counter = 1;
+    let (name, mut info_file) = loop {
+        let name = if counter == 1 {
+            file_name.clone()
+        } else {
+            format!("{}.{}", file_name, counter)
+        };
+        let info_path = info_dir.join(format!("{}.trashinfo", name));
+
+        match std::fs::File::create_new(&info_path) {
+            Ok(file) if !files_dir.join(&name).exists() => break (name, file),
+            Ok(_) => {
+                let _ = std::fs::remove_file(&info_path);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(trash_err(e)),
+        }
+        counter += 1;
+    };
+
+    let entry = TrashEntry {
+        trash_dir,
+        name,
+        original_path: path.clone(),
+        deletion_date: Some(chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string()),
+        is_dir,
+    };
+
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&stored_path),
+        entry.deletion_date.as_deref().unwrap_or_default()
+    );
+
+    let moved = info_file
+        .write_all(info.as_bytes())
+        .and_then(|()| std::fs::rename(&path, entry.files_path()));
+    if let Err(e) = moved {
+        let _ = std::fs::remove_file(entry.info_path());
+        return Err(trash_err(e));
+    }
+
+    Ok(entry)
+}
+
+// Every trash directory that currently exists for this user
+fn trash_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![home_trash()];
+
+    #[cfg(unix)]
+    if let Ok(uid) = current_uid() {
+        let mounts = std::fs::read_to_string("/proc/mounts").unwrap_or_default();
+        for mount_point in mounts.lines().filter_map(|line| line.split(' ').nth(1)) {
+            let top_dir = PathBuf::from(decode_mount_point(mount_point));
+            if let Some(trash) = top_dir_trash(&top_dir, uid, false)
+                && !dirs.contains(&trash)
+            {
+                dirs.push(trash);
+            }
+        }
+    }
+
+    dirs
+}
+
+// List the contents of all trash directories, newest first
+pub fn list() -> Vec<TrashEntry> {
+    let mut entries = Vec::new();
+
+    for trash_dir in trash_dirs() {
+        let Ok(infos) = std::fs::read_dir(trash_dir.join("info")) else {
+            continue;
+        };
+        let top_dir = top_dir_of(&trash_dir);
+
+        for info in infos.filter_map(|info| info.ok()) {
+            let file_name = info.file_name().to_string_lossy().into_owned();
+            let Some(name) = file_name.strip_suffix(".trashinfo") else {
+                continue;
+            };
+            let Ok(contents) = std::fs::read_to_string(info.path()) else {
+                continue;
+            };
+
+            let mut original_path = None;
+            let mut deletion_date = None;
+            for line in contents.lines() {
+                if let Some(value) = line.strip_prefix("Path=") {
+                    original_path = Some(PathBuf::from(decode_path(value)));
+                } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                    deletion_date = Some(value.to_string());
+                }
+            }
+            let Some(mut original_path) = original_path else {
+                continue;
+            };
+            if let Some(top_dir) = &top_dir
+                && original_path.is_relative()
+            {
+                original_path = top_dir.join(original_path);
+            }
+
+            let files_path = trash_dir.join("files").join(name);
+            entries.push(TrashEntry {
+                trash_dir: trash_dir.clone(),
+                name: name.to_string(),
+                original_path,
+                deletion_date,
+                is_dir: files_path.is_dir(),
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| b.deletion_date.cmp(&a.deletion_date));
+    entries
+}
+
+// Lists the trash on a thread of its own, reading every .trashinfo file takes a while when
+// there are many. Rescans asked for while one runs are done together after it.
+pub struct TrashLister {
+    rescan_tx: Sender<()>,
+    entries_rx: Receiver<Vec<TrashEntry>>,
+}
+
+impl TrashLister {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (rescan_tx, rescan_rx) = mpsc::channel::<()>();
+        let (entries_tx, entries_rx) = mpsc::channel();
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            while rescan_rx.recv().is_ok() {
+                while rescan_rx.try_recv().is_ok() {}
+                if entries_tx.send(list()).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+
+        Self {
+            rescan_tx,
+            entries_rx,
+        }
+    }
+
+    pub fn rescan(&self) {
+        let _ = self.rescan_tx.send(());
+    }
+
+    // The newest listing, if a rescan finished since the last call
+    pub fn poll(&self) -> Option<Vec<TrashEntry>> {
+        self.entries_rx.try_iter().last()
+    }
+}
+
+// Put an item back where it was deleted from
+pub fn restore(entry: &TrashEntry) -> FsResult<PathBuf> {
+    let restore_err = |e| FsError::new(FsOperation::Restore, &entry.original_path, e);
+
+    if std::fs::symlink_metadata(&entry.original_path).is_ok() {
+        return Err(restore_err(Error::new(
+            ErrorKind::AlreadyExists,
+            "an item with the same name already exists",
+        )));
+    }
+    if let Some(parent) = entry.original_path.parent() {
+        std::fs::create_dir_all(parent).map_err(restore_err)?;
+    }
+
+    std::fs::rename(entry.files_path(), &entry.original_path).map_err(restore_err)?;
+    let _ = std::fs::remove_file(entry.info_path());
+
+    Ok(entry.original_path.clone())
+}
+
+// Permanently delete a single trashed item
+pub fn delete_entry(entry: &TrashEntry) -> FsResult<()> {
+    let files_path = entry.files_path();
+    let delete_result = if entry.is_dir {
+        std::fs::remove_dir_all(&files_path)
+    } else {
+        std::fs::remove_file(&files_path)
+    };
+
+    match delete_result {
+        Ok(()) => {}
+        // Already gone, only the info file is left to clean up
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(FsError::new(FsOperation::EmptyTrash, &files_path, e)),
+    }
+
+    std::fs::remove_file(entry.info_path())
+        .map_err(|e| FsError::new(FsOperation::EmptyTrash, &entry.info_path(), e))
+}
+
+// Permanently delete everything in the trash, stops at the first failure
+pub fn empty() -> FsResult<()> {
+    for entry in list() {
+        delete_entry(&entry)?;
+    }
+    for trash_dir in trash_dirs() {
+        let _ = std::fs::remove_file(trash_dir.join("directorysizes"));
+    }
+    Ok(())
+}
+
+// Percent-encode a path like a URL path, keeping '/'
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn decode_path(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// /proc/mounts escapes spaces and friends as octal, e.g. "\040"
+#[cfg(unix)]
+fn decode_mount_point(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .and_then(|octal| std::str::from_utf8(octal).ok())
+            .and_then(|octal| u8::from_str_radix(octal, 8).ok());
+        match (bytes[i], octal) {
+            (b'\\', Some(byte)) => {
+                decoded.push(byte);
+                i += 4;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
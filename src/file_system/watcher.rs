@@ -0,0 +1,162 @@
+use super::file_tree::FileNode;
+use crate::utils::sorting::Collation;
+use eframe::egui;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+// Wait for a burst of events to settle before touching the tree,
+// but never hold changes back longer than MAX_DELAY
+const QUIET_PERIOD: Duration = Duration::from_millis(150);
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+// Watches the shown folders of every tab (and any loaded subfolders) and patches the trees
+// on change. A folder shown in several tabs is watched once.
+pub struct DirWatcher {
+    ctx: egui::Context,
+    watcher: Option<RecommendedWatcher>,
+    event_rx: Receiver<notify::Result<notify::Event>>,
+    watched: HashSet<PathBuf>,
+    pending: HashSet<PathBuf>,
+    rescan: HashSet<PathBuf>,
+    first_event: Option<Instant>,
+    last_event: Option<Instant>,
+}
+
+impl DirWatcher {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (event_tx, event_rx) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = event_tx.send(event);
+            repaint_ctx.request_repaint();
+        })
+        .map_err(|e| eprintln!("Failed to start file watcher: {}", e))
+        .ok();
+
+        Self {
+            ctx: ctx.clone(),
+            watcher,
+            event_rx,
+            watched: HashSet::new(),
+            pending: HashSet::new(),
+            rescan: HashSet::new(),
+            first_event: None,
+            last_event: None,
+        }
+    }
+
+    // Watch exactly the folders of the trees whose children are loaded
+    pub fn sync<'a>(&mut self, trees: impl IntoIterator<Item = &'a FileNode>) {
+        let Some(watcher) = self.watcher.as_mut() else {
+            return;
+        };
+
+        let mut wanted = HashSet::new();
+        for root in trees {
+            collect_loaded_dirs(root, &mut wanted);
+        }
+
+        for path in self.watched.difference(&wanted) {
+            let _ = watcher.unwatch(path);
+        }
+
+        let mut watched = HashSet::new();
+        for path in wanted {
+            if self.watched.contains(&path)
+                || watcher.watch(&path, RecursiveMode::NonRecursive).is_ok()
+            {
+                watched.insert(path);
+            }
+        }
+        self.watched = watched;
+    }
+
+    // Apply coalesced changes to every tree showing them, returns folders that must be listed
+    // again from scratch
+    pub fn poll<'a>(
+        &mut self,
+        trees: impl IntoIterator<Item = &'a mut FileNode>,
+        collation: Collation,
+    ) -> Vec<PathBuf> {
+        while let Ok(event) = self.event_rx.try_recv() {
+            let now = Instant::now();
+            self.first_event.get_or_insert(now);
+            self.last_event = Some(now);
+
+            match event {
+                Ok(event) if event.need_rescan() => {
+                    self.rescan.extend(self.watched.iter().cloned());
+                }
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(event) => self.pending.extend(event.paths),
+                Err(e) => {
+                    eprintln!("File watcher error: {}", e);
+                    self.rescan.extend(self.watched.iter().cloned());
+                }
+            }
+        }
+
+        let (Some(first), Some(last)) = (self.first_event, self.last_event) else {
+            return Vec::new();
+        };
+        if last.elapsed() < QUIET_PERIOD && first.elapsed() < MAX_DELAY {
+            self.ctx
+                .request_repaint_after(QUIET_PERIOD.saturating_sub(last.elapsed()));
+            return Vec::new();
+        }
+
+        self.first_event = None;
+        self.last_event = None;
+
+        let rescan: Vec<PathBuf> = self.rescan.drain().collect();
+        let mut deferred = HashSet::new();
+
+        let mut trees: Vec<&mut FileNode> = trees.into_iter().collect();
+        for path in self.pending.drain() {
+            let Some(parent_path) = path.parent() else {
+                continue;
+            };
+            if rescan.iter().any(|p| p == parent_path) {
+                continue;
+            }
+            let mut parents: Vec<&mut FileNode> = trees
+                .iter_mut()
+                .filter_map(|root| root.find_mut(parent_path))
+                .collect();
+
+            // Entries may still arrive from the loader, patch once it's done
+            if parents.iter().any(|parent| parent.loading) {
+                deferred.insert(path);
+                continue;
+            }
+            for parent in &mut parents {
+                parent.refresh_child(&path, collation);
+            }
+        }
+
+        if !deferred.is_empty() {
+            self.pending = deferred;
+            self.first_event = Some(Instant::now());
+            self.last_event = self.first_event;
+            self.ctx.request_repaint_after(QUIET_PERIOD);
+        }
+
+        rescan
+    }
+}
+
+fn collect_loaded_dirs(node: &FileNode, dirs: &mut HashSet<PathBuf>) {
+    if !node.is_dir {
+        return;
+    }
+    if let Some(children) = &node.children {
+        dirs.insert(node.path.clone());
+        for child in children {
+            collect_loaded_dirs(child, dirs);
+        }
+    }
+}
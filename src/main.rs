@@ -2,24 +2,46 @@
 #![allow(rustdoc::missing_crate_level_docs)]
 
 mod app;
+mod config;
 mod file_system;
+mod tab;
 mod ui;
 mod utils;
 mod views;
 
 fn main() -> eframe::Result {
-    let args: Vec<String> = std::env::args().collect();
-    let initial_path = if args.len() > 1 {
-        let path_str = if args[1].starts_with("~/") {
-            args[1].replacen(
+    // rustplorer [--config FILE] [PATH]
+    let mut args = std::env::args().skip(1);
+    let mut config_path = None;
+    let mut path_arg = None;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            config_path = args.next().map(std::path::PathBuf::from);
+            if config_path.is_none() {
+                eprintln!("Warning: --config needs a file. Using the default config.");
+            }
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            config_path = Some(std::path::PathBuf::from(path));
+        } else if path_arg.is_none() {
+            path_arg = Some(arg);
+        }
+    }
+    // Watcher events carry absolute paths
+    let config_path = config_path
+        .and_then(|path| std::path::absolute(path).ok())
+        .unwrap_or_else(config::default_path);
+
+    let initial_path = if let Some(arg) = path_arg {
+        let path_str = if arg.starts_with("~/") {
+            arg.replacen(
                 "~",
                 &std::env::var("HOME").unwrap_or_else(|_| ".".to_string()),
                 1,
             )
-        } else if args[1] == "~" {
+        } else if arg == "~" {
             std::env::var("HOME").unwrap_or_else(|_| ".".to_string())
         } else {
-            args[1].clone()
+            arg.clone()
         };
 
         let path = std::path::PathBuf::from(&path_str);
@@ -33,7 +55,7 @@ fn main() -> eframe::Result {
         } else {
             eprintln!(
                 "Warning: Path '{}' does not exist. Using current directory.",
-                args[1]
+                arg
             );
             std::env::current_dir().unwrap_or_else(|_| "./".into())
         }
@@ -53,6 +75,6 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "Rustplorer",
         options,
-        Box::new(move |cc| Ok(Box::new(app::MyApp::new(cc, initial_path)))),
+        Box::new(move |cc| Ok(Box::new(app::MyApp::new(cc, initial_path, config_path)))),
     )
 }
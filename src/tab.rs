@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use crate::file_system::{file_tree::FileNode, loader::DirLoader};
+use crate::ui::{path_bar::PathBar, settings::View};
+use crate::utils::sorting::Collation;
+use crate::views::ViewState;
+
+// A folder shown in its own tab. Each tab has its own history, listing and way of showing it.
+// The app's loader lists its folders under the tab's id.
+pub struct Tab {
+    pub id: u64,
+    pub current_root: PathBuf,
+    pub history: Vec<PathBuf>,
+    pub history_index: usize,
+    pub file_tree: FileNode,
+    pub view_state: ViewState,
+    pub view: View,
+    pub path_bar: PathBar,
+    pub show_trash: bool,
+    // What the tree's folders are ordered by
+    collation: Collation,
+}
+
+// What's kept of a closed tab so it can be reopened
+pub struct ClosedTab {
+    history: Vec<PathBuf>,
+    history_index: usize,
+    view: View,
+}
+
+impl Tab {
+    pub fn new(id: u64, path: PathBuf, view: View, loader: &mut DirLoader) -> Self {
+        let mut tree = FileNode::new(&path);
+        loader.load(id, &mut tree);
+
+        Self {
+            id,
+            current_root: path.clone(),
+            history: vec![path],
+            history_index: 0,
+            file_tree: tree,
+            view_state: ViewState::default(),
+            view,
+            path_bar: PathBar::default(),
+            show_trash: false,
+            collation: Collation::default(),
+        }
+    }
+
+    // Bring a closed tab back where it left its history. Folders that are gone since are
+    // still listed, their load error says so.
+    pub fn reopen(id: u64, closed: ClosedTab, loader: &mut DirLoader) -> Self {
+        let path = closed.history[closed.history_index].clone();
+        let mut tab = Self::new(id, path, closed.view, loader);
+        tab.history = closed.history;
+        tab.history_index = closed.history_index;
+        tab
+    }
+
+    pub fn close(self, loader: &mut DirLoader) -> ClosedTab {
+        loader.cancel_tab(self.id);
+        ClosedTab {
+            history: self.history,
+            history_index: self.history_index,
+            view: self.view,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        if self.show_trash {
+            return "Trash".to_string();
+        }
+        self.current_root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.current_root.display().to_string())
+    }
+
+    // Put the tree back in order when the way names compare changed
+    pub fn set_collation(&mut self, collation: Collation) {
+        if collation != self.collation {
+            self.file_tree.resort(collation);
+            self.collation = collation;
+        }
+    }
+
+    // The selected items of the shown folder, or the focused one when nothing is selected
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        let selection = &self.view_state.selection;
+        let mut paths: Vec<PathBuf> = self
+            .file_tree
+            .children
+            .iter()
+            .flatten()
+            .filter(|child| selection.contains(&child.path))
+            .map(|child| child.path.clone())
+            .collect();
+        if paths.is_empty()
+            && let Some(focus) = selection.focus()
+        {
+            paths.push(focus.to_path_buf());
+        }
+        paths
+    }
+
+    // List a folder of the tree again, if this tab shows it
+    pub fn reload(&mut self, path: &Path, loader: &mut DirLoader) {
+        if let Some(node) = self.file_tree.find_mut(path) {
+            loader.load(self.id, node);
+        }
+    }
+
+    pub fn go_to_directory(&mut self, path: &Path, loader: &mut DirLoader) {
+        if path.is_dir() && path != self.current_root.as_path() {
+            if self.history_index < self.history.len().saturating_sub(1) {
+                self.history.truncate(self.history_index + 1);
+            }
+
+            self.history.push(path.to_path_buf());
+            self.history_index += 1;
+
+            self.load_root(path.to_path_buf(), loader);
+        }
+    }
+
+    pub fn go_back(&mut self, loader: &mut DirLoader) {
+        // Leaving the trash returns to the folder shown before it
+        if self.show_trash {
+            self.show_trash = false;
+            return;
+        }
+
+        if self.history_index > 0 {
+            self.history_index -= 1;
+            self.load_root(self.history[self.history_index].clone(), loader);
+        }
+    }
+
+    pub fn go_forward(&mut self, loader: &mut DirLoader) {
+        if self.history_index + 1 < self.history.len() {
+            self.jump_to_history(self.history_index + 1, loader);
+        }
+    }
+
+    pub fn go_up(&mut self, loader: &mut DirLoader) {
+        if let Some(parent) = self.current_root.parent().map(|p| p.to_path_buf()) {
+            self.go_to_directory(&parent, loader);
+        }
+    }
+
+    // Show an entry of the history without changing it, like going back or forward several steps
+    pub fn jump_to_history(&mut self, index: usize, loader: &mut DirLoader) {
+        if let Some(path) = self.history.get(index).cloned() {
+            self.history_index = index;
+            self.load_root(path, loader);
+        }
+    }
+
+    // Replace the shown folder, abandoning the listing of the previous one
+    fn load_root(&mut self, path: PathBuf, loader: &mut DirLoader) {
+        loader.cancel_tab(self.id);
+
+        let mut node = FileNode::new(&path);
+        loader.load(self.id, &mut node);
+        self.file_tree = node;
+        self.current_root = path;
+        self.view_state = ViewState::default();
+        self.show_trash = false;
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.show_trash || self.history_index > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.history_index + 1 < self.history.len()
+    }
+
+    pub fn can_go_up(&self) -> bool {
+        !self.show_trash && self.current_root.parent().is_some()
+    }
+}
@@ -0,0 +1,370 @@
+use eframe::egui::{self, Color32};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+// What the app draws that egui's visuals have no field for
+#[derive(Debug, Clone, Copy)]
+pub struct ThemeExtras {
+    pub drop_target: Color32,
+    pub item_radius: u8,
+    pub frame_radius: u8,
+}
+
+impl Default for ThemeExtras {
+    fn default() -> Self {
+        Self {
+            drop_target: Color32::from_rgb(100, 200, 255),
+            item_radius: 5,
+            frame_radius: 10,
+        }
+    }
+}
+
+impl ThemeExtras {
+    fn id() -> egui::Id {
+        egui::Id::new("theme_extras")
+    }
+
+    pub fn get(ctx: &egui::Context) -> Self {
+        ctx.data(|data| data.get_temp(Self::id()))
+            .unwrap_or_default()
+    }
+
+    fn set(self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::id(), self));
+    }
+}
+
+// A theme file. Everything is optional, what's left out comes from egui's light or dark look.
+//
+//     name = "Nord"
+//     dark = true
+//
+//     [colors]
+//     window = "#2e3440"
+//     panel = "#3b4252"
+//     text = "#eceff4"
+//     accent = "#88c0d0"
+//     selection = "#4c566a"
+//     drop_target = "#a3be8c"
+//     error = "#bf616a"
+//
+//     [radius]
+//     widgets = 3
+//     windows = 8
+//     items = 4
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SchemeFile {
+    name: Option<String>,
+    dark: bool,
+    colors: SchemeColors,
+    radius: SchemeRadius,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SchemeColors {
+    window: Option<String>,
+    panel: Option<String>,
+    text: Option<String>,
+    accent: Option<String>,
+    selection: Option<String>,
+    drop_target: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SchemeRadius {
+    widgets: Option<u8>,
+    windows: Option<u8>,
+    items: Option<u8>,
+}
+
+fn parse_color(key: &str, value: &Option<String>) -> io::Result<Option<Color32>> {
+    value
+        .as_deref()
+        .map(|hex| {
+            Color32::from_hex(hex).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} isn't a #rrggbb color: {}", key, hex),
+                )
+            })
+        })
+        .transpose()
+}
+
+pub struct ColorScheme {
+    pub name: String,
+    visuals: egui::Visuals,
+    extras: ThemeExtras,
+}
+
+impl ColorScheme {
+    fn parse(path: &Path, contents: &str) -> io::Result<Self> {
+        let file: SchemeFile =
+            toml::from_str(contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let colors = &file.colors;
+
+        let mut visuals = if file.dark {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        let mut extras = ThemeExtras::default();
+
+        if let Some(color) = parse_color("window", &colors.window)? {
+            visuals.window_fill = color;
+        }
+        if let Some(color) = parse_color("panel", &colors.panel)? {
+            visuals.panel_fill = color;
+        }
+        if let Some(color) = parse_color("text", &colors.text)? {
+            visuals.override_text_color = Some(color);
+        }
+        if let Some(color) = parse_color("accent", &colors.accent)? {
+            visuals.hyperlink_color = color;
+            visuals.selection.stroke.color = color;
+            visuals.widgets.active.bg_fill = color;
+        }
+        if let Some(color) = parse_color("selection", &colors.selection)? {
+            visuals.selection.bg_fill = color;
+        }
+        if let Some(color) = parse_color("drop_target", &colors.drop_target)? {
+            extras.drop_target = color;
+        }
+        if let Some(color) = parse_color("error", &colors.error)? {
+            visuals.error_fg_color = color;
+        }
+
+        if let Some(radius) = file.radius.widgets {
+            let widgets = &mut visuals.widgets;
+            for widget in [
+                &mut widgets.noninteractive,
+                &mut widgets.inactive,
+                &mut widgets.hovered,
+                &mut widgets.active,
+                &mut widgets.open,
+            ] {
+                widget.corner_radius = radius.into();
+            }
+        }
+        if let Some(radius) = file.radius.windows {
+            visuals.window_corner_radius = radius.into();
+            visuals.menu_corner_radius = radius.into();
+            extras.frame_radius = radius;
+        }
+        if let Some(radius) = file.radius.items {
+            extras.item_radius = radius;
+        }
+
+        let name = file.name.unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        Ok(Self {
+            name,
+            visuals,
+            extras,
+        })
+    }
+
+    pub fn apply(&self, ctx: &egui::Context) {
+        ctx.set_visuals(self.visuals.clone());
+        self.extras.set(ctx);
+    }
+
+    // A row of the scheme's main colors
+    fn swatches(&self, ui: &mut egui::Ui) {
+        let visuals = &self.visuals;
+        let colors = [
+            visuals.window_fill,
+            visuals.panel_fill,
+            visuals.text_color(),
+            visuals.selection.stroke.color,
+            visuals.selection.bg_fill,
+            self.extras.drop_target,
+        ];
+        for color in colors {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+            ui.painter().rect_filled(rect, 2, color);
+            ui.painter().rect_stroke(
+                rect,
+                2,
+                ui.visuals().widgets.noninteractive.bg_stroke,
+                egui::StrokeKind::Inside,
+            );
+        }
+    }
+}
+
+// Built-in light and dark looks of egui
+pub fn reset(ctx: &egui::Context, visuals: egui::Visuals) {
+    ctx.set_visuals(visuals);
+    ThemeExtras::default().set(ctx);
+}
+
+// The theme files of the themes folder, read again whenever one of them changes
+pub struct ColorSchemes {
+    dir: PathBuf,
+    schemes: Vec<ColorScheme>,
+    // Files that couldn't be read, shown under the picker
+    errors: Vec<String>,
+    // Scheme hovered in the picker, shown in place of the chosen one
+    preview: Option<String>,
+    watcher: Option<RecommendedWatcher>,
+    watching: bool,
+    event_rx: Receiver<notify::Result<notify::Event>>,
+}
+
+impl ColorSchemes {
+    pub fn new(ctx: &egui::Context, dir: PathBuf) -> Self {
+        let (event_tx, event_rx) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = event_tx.send(event);
+            repaint_ctx.request_repaint();
+        })
+        .map_err(|e| eprintln!("Failed to start theme watcher: {}", e))
+        .ok();
+
+        let mut schemes = Self {
+            dir,
+            schemes: Vec::new(),
+            errors: Vec::new(),
+            preview: None,
+            watcher,
+            watching: false,
+            event_rx,
+        };
+        schemes.reload();
+        schemes
+    }
+
+    // Read every .toml file of the folder. A missing folder just has no themes.
+    fn reload(&mut self) {
+        if let Some(watcher) = self.watcher.as_mut()
+            && !self.watching
+        {
+            self.watching = watcher
+                .watch(&self.dir, RecursiveMode::NonRecursive)
+                .is_ok();
+        }
+
+        self.schemes.clear();
+        self.errors.clear();
+
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let scheme = std::fs::read_to_string(&path)
+                .and_then(|contents| ColorScheme::parse(&path, &contents));
+            match scheme {
+                Ok(scheme) if self.get(&scheme.name).is_none() => self.schemes.push(scheme),
+                Ok(scheme) => self.errors.push(format!(
+                    "{}: another theme is named {}",
+                    path.display(),
+                    scheme.name
+                )),
+                Err(e) => self.errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+    }
+
+    // Pick up edited theme files, so they can be tweaked while looking at the result
+    pub fn poll(&mut self) {
+        let mut changed = false;
+        while let Ok(event) = self.event_rx.try_recv() {
+            changed |= event.is_ok_and(|event| !matches!(event.kind, EventKind::Access(_)));
+        }
+        if changed {
+            self.reload();
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ColorScheme> {
+        self.schemes.iter().find(|scheme| scheme.name == name)
+    }
+
+    // The scheme to show: the one hovered in the picker last frame, otherwise the chosen one
+    pub fn current(&mut self, chosen: Option<&str>) -> Option<&ColorScheme> {
+        let preview = self.preview.take();
+        preview
+            .as_deref()
+            .or(chosen)
+            .and_then(|name| self.get(name))
+    }
+
+    // Combo box of the schemes, None standing for the built-in light and dark looks.
+    // Hovering an entry previews it.
+    pub fn picker(&mut self, ui: &mut egui::Ui, selected: &mut Option<String>) {
+        let mut hovered = None;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("color_scheme")
+                .selected_text(selected.as_deref().unwrap_or("Built-in"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(selected, None, "Built-in");
+                    for scheme in &self.schemes {
+                        ui.horizontal(|ui| {
+                            let entry = ui.selectable_value(
+                                selected,
+                                Some(scheme.name.clone()),
+                                &scheme.name,
+                            );
+                            scheme.swatches(ui);
+                            if entry.hovered() {
+                                hovered = Some(scheme.name.clone());
+                            }
+                        });
+                    }
+                });
+
+            if ui
+                .button(egui_phosphor::regular::ARROW_CLOCKWISE)
+                .on_hover_text("Read the theme files again")
+                .clicked()
+            {
+                self.reload();
+            }
+        });
+
+        if let Some(name) = hovered {
+            ui.ctx().request_repaint();
+            self.preview = Some(name);
+        }
+
+        if let Some(scheme) = selected.as_deref().and_then(|name| self.get(name)) {
+            ui.horizontal(|ui| scheme.swatches(ui));
+        } else if selected.is_some() {
+            ui.label(egui::RichText::new("This theme's file is gone").weak());
+        }
+
+        ui.label(
+            egui::RichText::new(format!("Theme files go in {}", self.dir.display()))
+                .weak()
+                .small(),
+        );
+        for error in &self.errors {
+            ui.label(
+                egui::RichText::new(error)
+                    .color(ui.visuals().error_fg_color)
+                    .small(),
+            );
+        }
+    }
+}
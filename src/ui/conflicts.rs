@@ -0,0 +1,218 @@
+use crate::file_system::{
+    formatting,
+    jobs::{Conflict, JobKind, Resolution},
+    metadata::{FileKind, FileMetadata},
+};
+use eframe::egui;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+
+pub enum ConflictOutcome {
+    // Sources resolved with Skip are already left out
+    Resolved {
+        kind: JobKind,
+        sources: Vec<PathBuf>,
+        resolutions: HashMap<PathBuf, Resolution>,
+    },
+    Cancelled,
+}
+
+// Asks what to do about each taken name of a copy or move before it's queued
+pub struct ConflictDialog {
+    kind: JobKind,
+    sources: Vec<PathBuf>,
+    conflicts: VecDeque<Conflict>,
+    resolutions: HashMap<PathBuf, Resolution>,
+    apply_to_all: bool,
+    comparing: bool,
+    // Existing and incoming item of the conflict shown, read when it comes up
+    compared: Option<(FileMetadata, FileMetadata)>,
+}
+
+fn kind_label(kind: FileKind) -> &'static str {
+    match kind {
+        FileKind::File => "File",
+        FileKind::Dir => "Folder",
+        FileKind::Symlink => "Link",
+        FileKind::Other => "Other",
+    }
+}
+
+fn compare_grid(ui: &mut egui::Ui, existing: &FileMetadata, incoming: &FileMetadata) {
+    egui::Grid::new("conflict_compare")
+        .num_columns(3)
+        .striped(true)
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label(egui::RichText::new("Existing").strong());
+            ui.label(egui::RichText::new("Incoming").strong());
+            ui.end_row();
+
+            ui.label("Kind");
+            ui.label(kind_label(existing.kind));
+            ui.label(kind_label(incoming.kind));
+            ui.end_row();
+
+            // Point out which side is bigger and which is newer
+            let bigger = |a: Option<u64>, b: Option<u64>| a.zip(b).is_some_and(|(a, b)| a > b);
+            let size_text = |size: Option<u64>, is_bigger: bool| {
+                let text = egui::RichText::new(formatting::format_optional_size(size));
+                if is_bigger { text.strong() } else { text }
+            };
+            ui.label("Size");
+            ui.label(size_text(
+                existing.size,
+                bigger(existing.size, incoming.size),
+            ));
+            ui.label(size_text(
+                incoming.size,
+                bigger(incoming.size, existing.size),
+            ));
+            ui.end_row();
+
+            let newer = |a: Option<std::time::SystemTime>, b: Option<std::time::SystemTime>| {
+                a.zip(b).is_some_and(|(a, b)| a > b)
+            };
+            let date_text = |time: Option<std::time::SystemTime>, is_newer: bool| {
+                let text = egui::RichText::new(formatting::format_date(time));
+                if is_newer { text.strong() } else { text }
+            };
+            ui.label("Modified");
+            ui.label(date_text(
+                existing.modified,
+                newer(existing.modified, incoming.modified),
+            ));
+            ui.label(date_text(
+                incoming.modified,
+                newer(incoming.modified, existing.modified),
+            ));
+            ui.end_row();
+        });
+}
+
+impl ConflictDialog {
+    pub fn new(kind: JobKind, sources: Vec<PathBuf>, conflicts: Vec<Conflict>) -> Self {
+        Self {
+            kind,
+            sources,
+            conflicts: conflicts.into(),
+            resolutions: HashMap::new(),
+            apply_to_all: false,
+            comparing: false,
+            compared: None,
+        }
+    }
+
+    fn resolve(&mut self, resolution: Resolution) {
+        let count = if self.apply_to_all {
+            self.conflicts.len()
+        } else {
+            1
+        };
+        for conflict in self.conflicts.drain(..count) {
+            self.resolutions.insert(conflict.source, resolution);
+        }
+        self.compared = None;
+    }
+
+    fn finish(&mut self) -> ConflictOutcome {
+        let mut resolutions = std::mem::take(&mut self.resolutions);
+        let sources = std::mem::take(&mut self.sources)
+            .into_iter()
+            .filter(|source| resolutions.get(source) != Some(&Resolution::Skip))
+            .collect();
+        resolutions.retain(|_, resolution| *resolution != Resolution::Skip);
+
+        ConflictOutcome::Resolved {
+            kind: self.kind.clone(),
+            sources,
+            resolutions,
+        }
+    }
+
+    // Returns the outcome once every conflict is answered or the dialog is cancelled
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<ConflictOutcome> {
+        let Some(conflict) = self.conflicts.front().cloned() else {
+            return Some(self.finish());
+        };
+
+        let mut choice = None;
+        let mut cancelled = false;
+
+        let name = conflict
+            .target
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let folder = conflict
+            .target
+            .parent()
+            .map(|parent| parent.display().to_string())
+            .unwrap_or_default();
+
+        let (existing, incoming) = self.compared.get_or_insert_with(|| {
+            (
+                FileMetadata::read(&conflict.existing),
+                FileMetadata::read(&conflict.source),
+            )
+        });
+
+        egui::Window::new("Item Already Exists")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if conflict.existing == conflict.target {
+                    ui.label(format!("'{}' already exists in this folder.", name));
+                } else {
+                    ui.label(format!(
+                        "Another item named '{}' goes to this folder too.",
+                        name
+                    ));
+                }
+                ui.label(egui::RichText::new(folder).monospace().weak());
+
+                ui.add_space(4.0);
+                ui.toggle_value(&mut self.comparing, "Compare");
+                if self.comparing {
+                    compare_grid(ui, existing, incoming);
+                }
+
+                let remaining = self.conflicts.len();
+                if remaining > 1 {
+                    ui.checkbox(
+                        &mut self.apply_to_all,
+                        format!("Apply to all {} remaining conflicts", remaining),
+                    );
+                }
+
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                    if ui.button("Skip").clicked() {
+                        choice = Some(Resolution::Skip);
+                    }
+                    if ui.button("Keep Both").clicked() {
+                        choice = Some(Resolution::KeepBoth);
+                    }
+                    if ui.button("Replace").clicked() {
+                        choice = Some(Resolution::Replace);
+                    }
+                });
+            });
+
+        if cancelled {
+            return Some(ConflictOutcome::Cancelled);
+        }
+        if let Some(resolution) = choice {
+            self.resolve(resolution);
+            if self.conflicts.is_empty() {
+                return Some(self.finish());
+            }
+        }
+        None
+    }
+}
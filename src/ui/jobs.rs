@@ -0,0 +1,116 @@
+use crate::file_system::{formatting::format_file_size, jobs::JobQueue};
+use eframe::egui;
+use std::sync::atomic::Ordering;
+
+fn format_eta(eta: std::time::Duration) -> String {
+    let secs = eta.as_secs();
+    if secs < 60 {
+        format!("{}s left", secs.max(1))
+    } else if secs < 3600 {
+        format!("{}m {}s left", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m left", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+// Collapsible list of queued and running jobs with their progress
+pub fn show_jobs_panel(ui: &mut egui::Ui, queue: &mut JobQueue) {
+    let mut pause_request = None;
+    let mut cancel_request = None;
+
+    egui::CollapsingHeader::new(format!("Operations ({})", queue.jobs().len()))
+        .id_salt("operations_panel")
+        .default_open(true)
+        .show(ui, |ui| {
+            for job in queue.jobs() {
+                let progress = &job.progress;
+                let is_paused = progress.is_paused();
+                let is_cancelled = progress.is_cancelled();
+
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(&job.title).strong());
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui
+                            .add_enabled(
+                                !is_cancelled,
+                                egui::Button::new(egui_phosphor::regular::X),
+                            )
+                            .on_hover_text("Cancel")
+                            .clicked()
+                        {
+                            cancel_request = Some(job.id);
+                        }
+
+                        let (icon, hover) = if is_paused {
+                            (egui_phosphor::regular::PLAY, "Resume")
+                        } else {
+                            (egui_phosphor::regular::PAUSE, "Pause")
+                        };
+                        if ui
+                            .add_enabled(!is_cancelled, egui::Button::new(icon))
+                            .on_hover_text(hover)
+                            .clicked()
+                        {
+                            pause_request = Some((job.id, !is_paused));
+                        }
+                    });
+                });
+
+                let bytes_total = progress.bytes_total.load(Ordering::Relaxed);
+                let mut status = format!(
+                    "{} of {} items",
+                    progress.items_done.load(Ordering::Relaxed),
+                    progress.items_total.load(Ordering::Relaxed)
+                );
+                if bytes_total > 0 {
+                    status = format!(
+                        "{} of {}, {}",
+                        format_file_size(progress.bytes_done.load(Ordering::Relaxed)),
+                        format_file_size(bytes_total),
+                        status
+                    );
+                }
+
+                let state = if is_cancelled {
+                    "Cancelling…".to_string()
+                } else if is_paused {
+                    "Paused".to_string()
+                } else if !progress.has_started() {
+                    "Waiting".to_string()
+                } else {
+                    job.eta().map(format_eta).unwrap_or_default()
+                };
+
+                ui.add(
+                    egui::ProgressBar::new(progress.fraction())
+                        .text(status)
+                        .desired_height(16.0),
+                );
+                ui.horizontal(|ui| {
+                    if let Some(current) = progress.current() {
+                        ui.label(
+                            egui::RichText::new(current.display().to_string())
+                                .monospace()
+                                .weak(),
+                        );
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.label(egui::RichText::new(state).weak());
+                    });
+                });
+                ui.add_space(4.0);
+            }
+        });
+
+    if let Some((id, paused)) = pause_request {
+        queue.set_paused(id, paused);
+    }
+    if let Some(id) = cancel_request {
+        queue.cancel(id);
+    }
+
+    // Progress lives in atomics, so keep redrawing while jobs run
+    ui.ctx()
+        .request_repaint_after(std::time::Duration::from_millis(100));
+}
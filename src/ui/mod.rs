@@ -1,2 +1,10 @@
+pub mod color_schemes;
+pub mod conflicts;
+pub mod jobs;
+pub mod notifications;
+pub mod path_bar;
+pub mod places;
 pub mod settings;
+pub mod system_theme;
+pub mod tabs;
 pub mod window;
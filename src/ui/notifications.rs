@@ -0,0 +1,139 @@
+use crate::file_system::{error::FsError, operations::FileAction};
+use eframe::egui;
+
+const MAX_TOASTS: usize = 5;
+// Seconds an info toast stays up, errors stay until dismissed
+const INFO_TIMEOUT: f64 = 6.0;
+
+#[derive(PartialEq)]
+enum ToastKind {
+    Error,
+    Info,
+}
+
+struct Toast {
+    id: u64,
+    kind: ToastKind,
+    title: String,
+    path: Option<String>,
+    message: Option<String>,
+    action: Option<(&'static str, FileAction)>,
+    // Set the first time the toast is drawn
+    shown_at: Option<f64>,
+}
+
+// Stack of toasts in the bottom right corner reporting finished and failed operations
+#[derive(Default)]
+pub struct Notifications {
+    toasts: Vec<Toast>,
+    next_id: u64,
+}
+
+impl Notifications {
+    fn push(&mut self, toast: Toast) {
+        if self.toasts.len() >= MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+        self.toasts.push(toast);
+        self.next_id += 1;
+    }
+
+    pub fn error(&mut self, error: FsError, retry: Option<FileAction>) {
+        self.push(Toast {
+            id: self.next_id,
+            kind: ToastKind::Error,
+            title: error.operation.failure_title().to_string(),
+            path: Some(error.path.display().to_string()),
+            message: Some(error.source.to_string()),
+            action: retry.map(|retry| ("Retry", retry)),
+            shown_at: None,
+        });
+    }
+
+    // Only the latest info toast is kept, so its button always refers to the last operation
+    pub fn info(&mut self, title: String, action: Option<(&'static str, FileAction)>) {
+        self.toasts.retain(|toast| toast.kind != ToastKind::Info);
+        self.push(Toast {
+            id: self.next_id,
+            kind: ToastKind::Info,
+            title,
+            path: None,
+            message: None,
+            action,
+            shown_at: None,
+        });
+    }
+
+    // Draw the toasts, returns the action of a clicked toast button
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<FileAction> {
+        let now = ctx.input(|i| i.time);
+        for toast in &mut self.toasts {
+            toast.shown_at.get_or_insert(now);
+        }
+        self.toasts.retain(|toast| {
+            toast.kind == ToastKind::Error
+                || toast
+                    .shown_at
+                    .is_some_and(|shown| now - shown < INFO_TIMEOUT)
+        });
+
+        if self.toasts.is_empty() {
+            return None;
+        }
+        if self
+            .toasts
+            .iter()
+            .any(|toast| toast.kind == ToastKind::Info)
+        {
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
+        }
+
+        let mut action_request = None;
+        let mut dismissed = Vec::new();
+
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(320.0);
+
+                for toast in &self.toasts {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            if toast.kind == ToastKind::Error {
+                                ui.label(
+                                    egui::RichText::new(egui_phosphor::regular::WARNING)
+                                        .color(ui.visuals().error_fg_color)
+                                        .size(18.0),
+                                );
+                            }
+                            ui.label(egui::RichText::new(&toast.title).strong());
+                        });
+                        if let Some(path) = &toast.path {
+                            ui.label(egui::RichText::new(path).monospace().weak());
+                        }
+                        if let Some(message) = &toast.message {
+                            ui.label(message);
+                        }
+
+                        ui.horizontal(|ui| {
+                            if let Some((label, action)) = &toast.action
+                                && ui.button(*label).clicked()
+                            {
+                                action_request = Some(action.clone());
+                                dismissed.push(toast.id);
+                            }
+                            if ui.button("Dismiss").clicked() {
+                                dismissed.push(toast.id);
+                            }
+                        });
+                    });
+                    ui.add_space(4.0);
+                }
+            });
+
+        self.toasts.retain(|toast| !dismissed.contains(&toast.id));
+
+        action_request
+    }
+}
@@ -0,0 +1,355 @@
+use eframe::egui;
+use eframe::egui::text::{CCursor, CCursorRange};
+use std::path::{Component, Path, PathBuf};
+
+// The nav bar's path: a breadcrumb of clickable folders that turns into a text field for
+// typing a path
+#[derive(Default)]
+pub struct PathBar {
+    editing: Option<PathEdit>,
+}
+
+struct PathEdit {
+    text: String,
+    // Focus the field and select everything on the first frame only
+    just_opened: bool,
+    // Candidates of the last Tab completion and the one shown, Tab again shows the next
+    cycle: Option<(Vec<String>, usize)>,
+    error: Option<String>,
+}
+
+// Expand a leading ~ and $VAR or ${VAR} references like a shell would. Unknown variables are
+// kept as typed.
+pub fn expand_path(text: &str) -> PathBuf {
+    let mut expanded = String::new();
+    let rest = match text.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            expanded.push_str(&std::env::var("HOME").unwrap_or_default());
+            rest
+        }
+        _ => text,
+    };
+
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+
+        let braced = chars.next_if_eq(&'{').is_some();
+        let mut name = String::new();
+        let mut closed = false;
+        while let Some(&c) = chars.peek() {
+            if braced {
+                if c == '}' {
+                    chars.next();
+                    closed = true;
+                    break;
+                }
+            } else if !(c.is_ascii_alphanumeric() || c == '_') {
+                break;
+            }
+            name.push(c);
+            chars.next();
+        }
+
+        match std::env::var(&name) {
+            Ok(value) if !name.is_empty() && closed == braced => expanded.push_str(&value),
+            _ if braced => {
+                expanded.push_str("${");
+                expanded.push_str(&name);
+                if closed {
+                    expanded.push('}');
+                }
+            }
+            _ => {
+                expanded.push('$');
+                expanded.push_str(&name);
+            }
+        }
+    }
+    PathBuf::from(expanded)
+}
+
+// Resolve typed text against the shown folder, dropping . and .. without following links
+fn resolve(text: &str, current: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in current.join(expand_path(text.trim())).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+// Folders inside dir, sorted by name
+fn child_dirs(dir: &Path, show_hidden: bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| show_hidden || !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort_by_key(|path| file_name(path).to_lowercase());
+    dirs
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// Each folder leading to path with the name shown for it. The home folder shows as ~ and
+// hides the folders above it.
+fn segments(path: &Path) -> Vec<(String, PathBuf)> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut segments = Vec::new();
+    let mut prefix = PathBuf::new();
+
+    for component in path.components() {
+        prefix.push(component);
+        if home.as_deref() == Some(prefix.as_path()) {
+            segments.clear();
+            segments.push(("~".to_string(), prefix.clone()));
+            continue;
+        }
+        let name = match component {
+            Component::RootDir => "/".to_string(),
+            other => other.as_os_str().to_string_lossy().into_owned(),
+        };
+        segments.push((name, prefix.clone()));
+    }
+    segments
+}
+
+fn longest_common_prefix<'a>(names: &[&'a str]) -> &'a str {
+    let first = names.first().copied().unwrap_or_default();
+    let len = names.iter().skip(1).fold(first.len(), |len, name| {
+        first[..len]
+            .char_indices()
+            .zip(name.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(len.min(name.len()), |((i, _), _)| i)
+    });
+    &first[..len]
+}
+
+impl PathEdit {
+    fn new(current: &Path) -> Self {
+        Self {
+            text: current.display().to_string(),
+            just_opened: true,
+            cycle: None,
+            error: None,
+        }
+    }
+
+    // Complete the folder name being typed. Several matches complete as far as they agree,
+    // then Tab steps through them.
+    fn complete(&mut self, current: &Path, show_hidden: bool) {
+        if let Some((candidates, index)) = &mut self.cycle
+            && candidates.get(*index) == Some(&self.text)
+        {
+            *index = (*index + 1) % candidates.len();
+            self.text = candidates[*index].clone();
+            return;
+        }
+        self.cycle = None;
+
+        let (typed_dir, prefix) = match self.text.rfind('/') {
+            Some(i) => self.text.split_at(i + 1),
+            None => ("", self.text.as_str()),
+        };
+        let show_hidden = show_hidden || prefix.starts_with('.');
+        let names: Vec<String> = child_dirs(&resolve(typed_dir, current), show_hidden)
+            .iter()
+            .map(|dir| file_name(dir))
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        match names.as_slice() {
+            [] => {}
+            [name] => self.text = format!("{}{}/", typed_dir, name),
+            _ => {
+                let common = longest_common_prefix(&names);
+                if common.len() > prefix.len() {
+                    self.text = format!("{}{}", typed_dir, common);
+                } else {
+                    let candidates: Vec<String> = names
+                        .iter()
+                        .map(|name| format!("{}{}/", typed_dir, name))
+                        .collect();
+                    self.text = candidates[0].clone();
+                    self.cycle = Some((candidates, 0));
+                }
+            }
+        }
+    }
+}
+
+impl PathBar {
+    pub fn start_editing(&mut self, current: &Path) {
+        self.editing = Some(PathEdit::new(current));
+    }
+
+    // Returns the folder to go to when a segment, a sibling or a typed path was picked
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        current: &Path,
+        show_hidden: bool,
+    ) -> Option<PathBuf> {
+        let edit_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::L);
+        if !ui.ctx().wants_keyboard_input() && ui.input_mut(|i| i.consume_shortcut(&edit_shortcut))
+        {
+            self.start_editing(current);
+        }
+
+        if self.editing.is_some() {
+            self.show_editor(ui, current, show_hidden)
+        } else {
+            self.show_breadcrumbs(ui, current, show_hidden)
+        }
+    }
+
+    fn show_breadcrumbs(
+        &mut self,
+        ui: &mut egui::Ui,
+        current: &Path,
+        show_hidden: bool,
+    ) -> Option<PathBuf> {
+        let mut picked = None;
+        let segments = segments(current);
+        ui.spacing_mut().item_spacing.x = 2.0;
+
+        for (i, (name, path)) in segments.iter().enumerate() {
+            let is_last = i + 1 == segments.len();
+            let mut text = egui::RichText::new(name).monospace();
+            if is_last {
+                text = text.strong();
+            }
+            if ui.add(egui::Button::new(text).frame(false)).clicked() {
+                picked = Some(path.clone());
+            }
+
+            // The separator lists the folders next to the following segment
+            let separator = ui.add(
+                egui::Button::new(egui::RichText::new(egui_phosphor::regular::CARET_RIGHT).weak())
+                    .frame(false),
+            );
+            let next = segments.get(i + 1).map(|(_, next)| next);
+            egui::Popup::menu(&separator).show(|ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        let dirs = child_dirs(path, show_hidden);
+                        if dirs.is_empty() {
+                            ui.label(egui::RichText::new("No folders").weak());
+                        }
+                        for dir in dirs {
+                            if ui
+                                .selectable_label(Some(&dir) == next, file_name(&dir))
+                                .clicked()
+                            {
+                                picked = Some(dir);
+                                ui.close();
+                            }
+                        }
+                    });
+            });
+        }
+
+        // The empty space after the breadcrumb switches to typing a path
+        let rest = ui
+            .allocate_response(
+                egui::vec2(ui.available_width(), ui.spacing().interact_size.y),
+                egui::Sense::click(),
+            )
+            .on_hover_cursor(egui::CursorIcon::Text);
+        if rest.clicked() {
+            self.start_editing(current);
+        }
+
+        picked
+    }
+
+    fn show_editor(
+        &mut self,
+        ui: &mut egui::Ui,
+        current: &Path,
+        show_hidden: bool,
+    ) -> Option<PathBuf> {
+        let edit = self.editing.as_mut()?;
+        let mut picked = None;
+        let mut close = false;
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if let Some(error) = &edit.error {
+                ui.label(egui::RichText::new(error).color(ui.visuals().error_fg_color));
+            }
+
+            // Tab completes instead of moving the focus away
+            let mut output = egui::TextEdit::singleline(&mut edit.text)
+                .font(egui::TextStyle::Monospace)
+                .desired_width(ui.available_width())
+                .lock_focus(true)
+                .show(ui);
+
+            if output.response.changed() {
+                edit.error = None;
+            }
+
+            if std::mem::take(&mut edit.just_opened) {
+                output.response.request_focus();
+                output.state.cursor.set_char_range(Some(CCursorRange::two(
+                    CCursor::new(0),
+                    CCursor::new(edit.text.chars().count()),
+                )));
+                output.state.store(ui.ctx(), output.response.id);
+                return;
+            }
+
+            if output.response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Tab)) {
+                edit.complete(current, show_hidden);
+                edit.error = None;
+                let end = CCursor::new(edit.text.chars().count());
+                output
+                    .state
+                    .cursor
+                    .set_char_range(Some(CCursorRange::one(end)));
+                output.state.store(ui.ctx(), output.response.id);
+            }
+
+            if output.response.lost_focus() {
+                if ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter)) {
+                    let path = resolve(&edit.text, current);
+                    if path.is_dir() {
+                        picked = Some(path);
+                        close = true;
+                    } else {
+                        edit.error = Some(format!("No folder at {}", path.display()));
+                        output.response.request_focus();
+                    }
+                } else {
+                    // Escape or a click elsewhere
+                    close = true;
+                }
+            }
+        });
+
+        if close {
+            self.editing = None;
+        }
+        picked
+    }
+}
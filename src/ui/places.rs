@@ -0,0 +1,216 @@
+use crate::config::{config_home, home_dir};
+use crate::utils::drag_drop;
+use eframe::egui;
+use egui_phosphor::regular as icons;
+use std::path::{Path, PathBuf};
+
+pub enum PlacesRequest {
+    Open(PathBuf),
+    OpenInNewTab(PathBuf),
+    OpenTrash,
+}
+
+// A folder that's always in the sidebar
+struct BuiltinPlace {
+    icon: &'static str,
+    name: String,
+    path: PathBuf,
+}
+
+// The sidebar: Home and the XDG user folders, then the folders the user pinned, then the Trash.
+// Pinned folders are saved with the config.
+pub struct Places {
+    favorites: Vec<PathBuf>,
+    builtin: Vec<BuiltinPlace>,
+}
+
+// XDG user folders with their icons and the names used when user-dirs.dirs doesn't list them
+const USER_DIRS: [(&str, &str, &str); 6] = [
+    ("XDG_DESKTOP_DIR", "Desktop", icons::DESKTOP),
+    ("XDG_DOCUMENTS_DIR", "Documents", icons::FILE_TEXT),
+    ("XDG_DOWNLOAD_DIR", "Downloads", icons::DOWNLOAD_SIMPLE),
+    ("XDG_MUSIC_DIR", "Music", icons::MUSIC_NOTES),
+    ("XDG_PICTURES_DIR", "Pictures", icons::IMAGE),
+    ("XDG_VIDEOS_DIR", "Videos", icons::FILM_STRIP),
+];
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// Value of a KEY="$HOME/Folder" line of user-dirs.dirs
+fn user_dir(contents: &str, key: &str, home: &Path) -> Option<PathBuf> {
+    let value = contents.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix(key)?
+            .trim_start()
+            .strip_prefix('=')
+    })?;
+    let value = value.trim().trim_matches('"');
+
+    Some(match value.strip_prefix("$HOME") {
+        Some(rest) => home.join(rest.trim_start_matches('/')),
+        None => PathBuf::from(value),
+    })
+}
+
+fn builtin_places() -> Vec<BuiltinPlace> {
+    let home = home_dir();
+    let user_dirs =
+        std::fs::read_to_string(config_home().join("user-dirs.dirs")).unwrap_or_default();
+
+    let mut places = vec![BuiltinPlace {
+        icon: icons::HOUSE,
+        name: "Home".to_string(),
+        path: home.clone(),
+    }];
+    for (key, default_name, icon) in USER_DIRS {
+        let path = user_dir(&user_dirs, key, &home).unwrap_or_else(|| home.join(default_name));
+        // Unused folders point at home itself
+        if path != home && path.is_dir() {
+            places.push(BuiltinPlace {
+                icon,
+                name: file_name(&path),
+                path,
+            });
+        }
+    }
+    places
+}
+
+fn place_entry(
+    ui: &mut egui::Ui,
+    icon: &str,
+    name: &str,
+    path: &Path,
+    is_current: bool,
+    request: &mut Option<PlacesRequest>,
+) -> egui::Response {
+    let response = ui
+        .selectable_label(is_current, format!("{} {}", icon, name))
+        .on_hover_text(path.display().to_string());
+    if response.clicked() {
+        *request = Some(PlacesRequest::Open(path.to_path_buf()));
+    }
+    if response.middle_clicked() {
+        *request = Some(PlacesRequest::OpenInNewTab(path.to_path_buf()));
+    }
+    response
+}
+
+impl Places {
+    pub fn new(favorites: Vec<PathBuf>) -> Self {
+        Self {
+            favorites,
+            builtin: builtin_places(),
+        }
+    }
+
+    pub fn favorites(&self) -> &[PathBuf] {
+        &self.favorites
+    }
+
+    // Replace the pinned folders, e.g. after the config file was edited
+    pub fn set_favorites(&mut self, favorites: Vec<PathBuf>) {
+        self.favorites = favorites;
+    }
+
+    pub fn add(&mut self, path: PathBuf) {
+        if path.is_dir() && !self.favorites.contains(&path) {
+            self.favorites.push(path);
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.favorites.retain(|favorite| favorite != path);
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        current: &Path,
+        show_trash: bool,
+    ) -> Option<PlacesRequest> {
+        let mut request = None;
+        let area = ui.max_rect();
+
+        ui.heading("Places");
+        ui.separator();
+
+        for place in &self.builtin {
+            place_entry(
+                ui,
+                place.icon,
+                &place.name,
+                &place.path,
+                !show_trash && place.path == current,
+                &mut request,
+            );
+        }
+
+        ui.add_space(8.0);
+        ui.label(egui::RichText::new("Favorites").weak());
+
+        if self.favorites.is_empty() {
+            ui.label(
+                egui::RichText::new("Drop folders here to pin them")
+                    .weak()
+                    .small(),
+            );
+        }
+
+        let mut removed = None;
+        egui_dnd::dnd(ui, "favorites").show_vec(&mut self.favorites, |ui, path, handle, _state| {
+            handle.ui(ui, |ui| {
+                let entry = place_entry(
+                    ui,
+                    icons::FOLDER,
+                    &file_name(path),
+                    path,
+                    !show_trash && path.as_path() == current,
+                    &mut request,
+                );
+                entry.context_menu(|ui| {
+                    if ui.button("Open in New Tab").clicked() {
+                        request = Some(PlacesRequest::OpenInNewTab(path.clone()));
+                        ui.close();
+                    }
+                    if ui.button("Remove from Favorites").clicked() {
+                        removed = Some(path.clone());
+                        ui.close();
+                    }
+                });
+            });
+        });
+        if let Some(path) = removed {
+            self.remove(&path);
+        }
+
+        ui.add_space(8.0);
+        ui.separator();
+
+        let trash_label = format!("{} Trash", icons::TRASH);
+        if ui.selectable_label(show_trash, trash_label).clicked() {
+            request = Some(PlacesRequest::OpenTrash);
+        }
+
+        // Folders dragged out of a view get pinned
+        if drag_drop::is_dragging_over(ui, area) {
+            ui.painter().rect_stroke(
+                area,
+                4.0,
+                ui.visuals().selection.stroke,
+                egui::StrokeKind::Inside,
+            );
+        }
+        if let Some(paths) = drag_drop::take_drop(ui, area) {
+            for path in paths {
+                self.add(path);
+            }
+        }
+
+        request
+    }
+}
@@ -1,41 +1,107 @@
+use crate::ui::color_schemes::{self, ColorScheme, ColorSchemes};
+use crate::utils::sorting::Collation;
+use crate::views::columns::{self, ListColumn};
 use eframe::egui;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     pub show_hidden_files: bool,
     pub sort_folders_first: bool,
     pub sort_items: bool,
+    pub sort_key: SortKey,
+    pub sort_descending: bool,
+    pub list_columns: Vec<ListColumn>,
+    pub natural_sort: bool,
+    pub ignore_accents: bool,
+    pub locale_sort: bool,
     pub theme: Theme,
+    // Name of a theme file's scheme, used in place of the theme
+    pub color_scheme: Option<String>,
     pub view: View,
+    pub dual_pane: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Theme {
     Light,
     Dark,
     System,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum View {
     Grid,
     List,
 }
 
+// What items are ordered by, folders first or not
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    Name,
+    Size,
+    Modified,
+    Created,
+    Type,
+    Extension,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 6] = [
+        SortKey::Name,
+        SortKey::Size,
+        SortKey::Modified,
+        SortKey::Created,
+        SortKey::Type,
+        SortKey::Extension,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Name => "Name",
+            SortKey::Size => "Size",
+            SortKey::Modified => "Modified",
+            SortKey::Created => "Created",
+            SortKey::Type => "Type",
+            SortKey::Extension => "Extension",
+        }
+    }
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
             show_hidden_files: false,
             sort_folders_first: true,
             sort_items: true,
+            sort_key: SortKey::Name,
+            sort_descending: false,
+            list_columns: columns::default_columns(),
+            natural_sort: false,
+            ignore_accents: false,
+            locale_sort: false,
             theme: Theme::System,
+            color_scheme: None,
             view: View::List,
+            dual_pane: false,
         }
     }
 }
 
 impl Settings {
-    pub fn ui(&mut self, ctx: &egui::Context, open: &mut bool) {
+    pub fn collation(&self) -> Collation {
+        Collation {
+            natural: self.natural_sort,
+            ignore_accents: self.ignore_accents,
+            locale: self.locale_sort,
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context, open: &mut bool, schemes: &mut ColorSchemes) {
         egui::Window::new("Settings")
             .open(open)
             .collapsible(false)
@@ -52,6 +118,30 @@ impl Settings {
                 ui.checkbox(&mut self.sort_folders_first, "Sort folders first");
                 ui.add_space(4.0);
                 ui.checkbox(&mut self.sort_items, "Sort items");
+                ui.add_enabled_ui(self.sort_items, |ui| {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("sort_key")
+                            .selected_text(self.sort_key.label())
+                            .show_ui(ui, |ui| {
+                                for key in SortKey::ALL {
+                                    ui.selectable_value(&mut self.sort_key, key, key.label());
+                                }
+                            });
+                        ui.checkbox(&mut self.sort_descending, "Descending");
+                    });
+                });
+                ui.add_space(4.0);
+                ui.checkbox(&mut self.natural_sort, "Sort numbers by value")
+                    .on_hover_text("file2 comes before file10");
+                ui.add_space(4.0);
+                ui.checkbox(&mut self.ignore_accents, "Ignore accents when sorting")
+                    .on_hover_text("Été comes next to ete rather than after z");
+                ui.add_space(4.0);
+                ui.checkbox(
+                    &mut self.locale_sort,
+                    "Sort names by the rules of your language",
+                )
+                .on_hover_text("Uses the language set in LC_COLLATE or LANG");
 
                 ui.add_space(16.0);
 
@@ -60,18 +150,25 @@ impl Settings {
 
                 ui.add_space(8.0);
 
+                ui.label("Color scheme:");
+                schemes.picker(ui, &mut self.color_scheme);
+                ui.add_space(4.0);
                 ui.label("Theme:");
-                ui.horizontal(|ui| {
-                    ui.radio_value(&mut self.theme, Theme::Light, "Light");
-                    ui.radio_value(&mut self.theme, Theme::Dark, "Dark");
-                    ui.radio_value(&mut self.theme, Theme::System, "System");
+                ui.add_enabled_ui(self.color_scheme.is_none(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.theme, Theme::Light, "Light");
+                        ui.radio_value(&mut self.theme, Theme::Dark, "Dark");
+                        ui.radio_value(&mut self.theme, Theme::System, "System");
+                    });
                 });
                 ui.add_space(4.0);
-                ui.label("View:");
+                ui.label("View of new tabs:");
                 ui.horizontal(|ui| {
                     ui.radio_value(&mut self.view, View::Grid, "Grid");
                     ui.radio_value(&mut self.view, View::List, "List");
                 });
+                ui.add_space(4.0);
+                ui.checkbox(&mut self.dual_pane, "Two panes side by side");
 
                 ui.add_space(16.0);
 
@@ -84,17 +181,32 @@ impl Settings {
             });
     }
 
-    pub fn apply_theme(&self, ctx: &egui::Context) {
+    // system_dark is what the desktop prefers, see SystemTheme. A color scheme, chosen or
+    // previewed, replaces the theme.
+    pub fn apply_theme(
+        &self,
+        ctx: &egui::Context,
+        system_dark: Option<bool>,
+        scheme: Option<&ColorScheme>,
+    ) {
+        if let Some(scheme) = scheme {
+            scheme.apply(ctx);
+            return;
+        }
+
         match self.theme {
             Theme::Light => {
-                ctx.set_visuals(egui::Visuals::light());
+                color_schemes::reset(ctx, egui::Visuals::light());
             }
             Theme::Dark => {
-                ctx.set_visuals(egui::Visuals::dark());
-            }
-            Theme::System => {
-                // default
+                color_schemes::reset(ctx, egui::Visuals::dark());
             }
+            Theme::System => match system_dark {
+                Some(true) => color_schemes::reset(ctx, egui::Visuals::dark()),
+                Some(false) => color_schemes::reset(ctx, egui::Visuals::light()),
+                // No preference, keep egui's guess
+                None => color_schemes::reset(ctx, ctx.theme().default_visuals()),
+            },
         }
     }
 }
@@ -0,0 +1,150 @@
+use crate::config::config_home;
+use eframe::egui;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::sync::mpsc::{self, Receiver};
+
+// Newest first, the first one that says something wins
+const GTK_VERSIONS: [&str; 2] = ["gtk-4.0", "gtk-3.0"];
+
+// Follows whether the desktop prefers a dark or light look. The settings portal is asked
+// first and tells us when that changes, GTK's settings.ini is read and watched without it.
+pub struct SystemTheme {
+    dark_rx: Receiver<Option<bool>>,
+    dark: Option<bool>,
+}
+
+impl SystemTheme {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (dark_tx, dark_rx) = mpsc::channel();
+        let ctx = ctx.clone();
+
+        std::thread::spawn(move || {
+            // False once the app is gone
+            let send = |dark| {
+                ctx.request_repaint();
+                dark_tx.send(dark).is_ok()
+            };
+
+            #[cfg(target_os = "linux")]
+            if let Err(e) = portal::follow(&send) {
+                eprintln!("Settings portal unavailable, using GTK settings: {}", e);
+            } else {
+                return;
+            }
+            follow_gtk_settings(&send);
+        });
+
+        Self {
+            dark_rx,
+            dark: None,
+        }
+    }
+
+    // Whether the desktop prefers dark, None when it states no preference
+    pub fn prefers_dark(&mut self) -> Option<bool> {
+        while let Ok(dark) = self.dark_rx.try_recv() {
+            self.dark = dark;
+        }
+        self.dark
+    }
+}
+
+// gtk-application-prefer-dark-theme when it's set, otherwise a theme named like "Adwaita-dark"
+fn parse_gtk_settings(contents: &str) -> Option<bool> {
+    let value = |key: &str| {
+        contents.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            (name.trim() == key).then(|| value.trim())
+        })
+    };
+
+    let prefer_dark = value("gtk-application-prefer-dark-theme").map(|v| v == "1" || v == "true");
+    if prefer_dark == Some(true) {
+        return Some(true);
+    }
+    value("gtk-theme-name")
+        .map(|name| name.to_lowercase().contains("dark"))
+        .or(prefer_dark)
+}
+
+fn gtk_prefers_dark() -> Option<bool> {
+    GTK_VERSIONS.iter().find_map(|version| {
+        let path = config_home().join(version).join("settings.ini");
+        parse_gtk_settings(&std::fs::read_to_string(path).ok()?)
+    })
+}
+
+// Report the GTK preference, then again whenever settings.ini changes
+fn follow_gtk_settings(send: &impl Fn(Option<bool>) -> bool) {
+    if !send(gtk_prefers_dark()) {
+        return;
+    }
+
+    let (event_tx, event_rx) = mpsc::channel();
+    let Ok(mut watcher) = notify::recommended_watcher(event_tx) else {
+        return;
+    };
+    for version in GTK_VERSIONS {
+        let _ = watcher.watch(&config_home().join(version), RecursiveMode::NonRecursive);
+    }
+
+    for event in event_rx {
+        let changed = event.is_ok_and(|event| !matches!(event.kind, EventKind::Access(_)));
+        if changed && !send(gtk_prefers_dark()) {
+            return;
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod portal {
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::{OwnedValue, Value};
+
+    const NAMESPACE: &str = "org.freedesktop.appearance";
+    const KEY: &str = "color-scheme";
+
+    // color-scheme is 0 for no preference, 1 for dark and 2 for light
+    fn prefers_dark(value: &Value) -> Option<bool> {
+        match value {
+            Value::U32(1) => Some(true),
+            Value::U32(2) => Some(false),
+            // Read, the older call, wraps the value once more
+            Value::Value(inner) => prefers_dark(inner),
+            _ => None,
+        }
+    }
+
+    // Report the portal's preference, then every change of it. Without a preference GTK's
+    // is used. Fails when there's no portal, or one without the appearance settings.
+    pub fn follow(send: &impl Fn(Option<bool>) -> bool) -> zbus::Result<()> {
+        let connection = Connection::session()?;
+        let proxy = Proxy::new(
+            &connection,
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+        )?;
+
+        // Subscribe first so a change can't slip in before the read
+        let changes =
+            proxy.receive_signal_with_args("SettingChanged", &[(0, NAMESPACE), (1, KEY)])?;
+        let value: OwnedValue = proxy
+            .call("ReadOne", &(NAMESPACE, KEY))
+            .or_else(|_| proxy.call("Read", &(NAMESPACE, KEY)))?;
+        if !send(prefers_dark(&value).or_else(super::gtk_prefers_dark)) {
+            return Ok(());
+        }
+
+        for message in changes {
+            let Ok((_, _, value)) = message.body().deserialize::<(String, String, OwnedValue)>()
+            else {
+                continue;
+            };
+            if !send(prefers_dark(&value).or_else(super::gtk_prefers_dark)) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use crate::tab::Tab;
+use eframe::egui;
+
+pub enum TabCommand {
+    New,
+    Close(usize),
+    CloseOthers(usize),
+    Reopen,
+}
+
+const MAX_TAB_WIDTH: f32 = 160.0;
+
+// Row of tabs under the title bar. Clicking a tab switches to it, the cross or a middle-click
+// closes it and dragging reorders the tabs.
+pub fn show_tab_strip(
+    ui: &mut egui::Ui,
+    tabs: &mut [Tab],
+    active: &mut usize,
+    can_reopen: bool,
+) -> Option<TabCommand> {
+    let mut command = None;
+    let active_id = tabs[*active].id;
+    let can_close = tabs.len() > 1;
+    let ids: Vec<u64> = tabs.iter().map(|tab| tab.id).collect();
+    let titles: Vec<String> = tabs.iter().map(Tab::title).collect();
+
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+
+        let response =
+            egui_dnd::dnd(ui, "tab_strip").show(ids.iter().copied(), |ui, id, handle, state| {
+                let index = state.index;
+                handle.ui(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.set_max_width(MAX_TAB_WIDTH);
+                        let tab = ui
+                            .add(
+                                egui::Button::selectable(id == active_id, &titles[index])
+                                    .truncate(),
+                            )
+                            .on_hover_text(tabs[index].current_root.display().to_string());
+
+                        if tab.clicked() {
+                            *active = index;
+                        }
+                        if tab.middle_clicked() && can_close {
+                            command = Some(TabCommand::Close(index));
+                        }
+                        tab.context_menu(|ui| {
+                            if ui.button("New Tab").clicked() {
+                                command = Some(TabCommand::New);
+                                ui.close();
+                            }
+                            if ui
+                                .add_enabled(can_close, egui::Button::new("Close Tab"))
+                                .clicked()
+                            {
+                                command = Some(TabCommand::Close(index));
+                                ui.close();
+                            }
+                            if ui
+                                .add_enabled(can_close, egui::Button::new("Close Other Tabs"))
+                                .clicked()
+                            {
+                                command = Some(TabCommand::CloseOthers(index));
+                                ui.close();
+                            }
+                            if ui
+                                .add_enabled(can_reopen, egui::Button::new("Reopen Closed Tab"))
+                                .clicked()
+                            {
+                                command = Some(TabCommand::Reopen);
+                                ui.close();
+                            }
+                        });
+
+                        if can_close
+                            && ui
+                                .add(
+                                    egui::Button::new(egui_phosphor::regular::X)
+                                        .frame(false)
+                                        .small(),
+                                )
+                                .on_hover_text("Close tab")
+                                .clicked()
+                        {
+                            command = Some(TabCommand::Close(index));
+                        }
+                    });
+                });
+            });
+
+        // Keep the same tab active wherever it was dragged to
+        if let Some(update) = response.final_update() {
+            egui_dnd::utils::shift_vec(update.from, update.to, tabs);
+            *active = tabs
+                .iter()
+                .position(|tab| tab.id == active_id)
+                .unwrap_or_default();
+        }
+
+        if ui
+            .add(egui::Button::new(egui_phosphor::regular::PLUS).frame(false))
+            .on_hover_text("New tab")
+            .clicked()
+        {
+            command = Some(TabCommand::New);
+        }
+    });
+
+    command
+}
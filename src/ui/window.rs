@@ -1,3 +1,4 @@
+use crate::ui::color_schemes::ThemeExtras;
 use eframe::egui::{self, ViewportCommand};
 
 pub fn custom_window_frame(
@@ -10,7 +11,7 @@ pub fn custom_window_frame(
 
     let panel_frame = egui::Frame::new()
         .fill(ctx.style().visuals.window_fill())
-        .corner_radius(10)
+        .corner_radius(ThemeExtras::get(ctx).frame_radius)
         .stroke(ctx.style().visuals.widgets.noninteractive.fg_stroke)
         .outer_margin(1);
 
@@ -89,7 +90,7 @@ fn title_bar_ui(
 
             let settings_response = ui
                 .add(egui::Button::new(
-                    egui::RichText::new(format!("{}", egui_nerdfonts::regular::GEAR_1)).size(18.0),
+                    egui::RichText::new(egui_nerdfonts::regular::GEAR_1).size(18.0),
                 ))
                 .on_hover_text("Settings");
 
@@ -119,7 +120,7 @@ fn close_maximize_minimize(ui: &mut egui::Ui) {
 
     let close_response = ui
         .add(Button::new(
-            RichText::new(format!("{}", egui_phosphor::regular::X)).size(button_height),
+            RichText::new(egui_phosphor::regular::X).size(button_height),
         ))
         .on_hover_text("Close the window");
     if close_response.clicked() {
@@ -130,8 +131,7 @@ fn close_maximize_minimize(ui: &mut egui::Ui) {
     if is_maximized {
         let maximized_response = ui
             .add(Button::new(
-                RichText::new(format!("{}", egui_phosphor::regular::CORNERS_IN))
-                    .size(button_height),
+                RichText::new(egui_phosphor::regular::CORNERS_IN).size(button_height),
             ))
             .on_hover_text("Restore window");
         if maximized_response.clicked() {
@@ -141,8 +141,7 @@ fn close_maximize_minimize(ui: &mut egui::Ui) {
     } else {
         let maximized_response = ui
             .add(Button::new(
-                RichText::new(format!("{}", egui_phosphor::regular::CORNERS_OUT))
-                    .size(button_height),
+                RichText::new(egui_phosphor::regular::CORNERS_OUT).size(button_height),
             ))
             .on_hover_text("Maximize window");
         if maximized_response.clicked() {
@@ -152,7 +151,7 @@ fn close_maximize_minimize(ui: &mut egui::Ui) {
 
     let minimized_response = ui
         .add(Button::new(
-            RichText::new(format!("{}", egui_phosphor::regular::CARET_DOWN)).size(button_height),
+            RichText::new(egui_phosphor::regular::CARET_DOWN).size(button_height),
         ))
         .on_hover_text("Minimize the window");
     if minimized_response.clicked() {
@@ -1,4 +1,20 @@
 use eframe::egui;
+use std::path::PathBuf;
+
+// Items being dragged out of a view, so the other pane can take the drop
+pub struct DraggedPaths {
+    pub source: egui::Id,
+    pub paths: Vec<PathBuf>,
+}
+
+// The folder under the pointer, as the index of the child it shows
+fn folder_at_pointer(ui: &egui::Ui, folder_rects: &[(usize, usize, egui::Rect)]) -> Option<usize> {
+    let pos = ui.input(|i| i.pointer.hover_pos())?;
+    folder_rects
+        .iter()
+        .find(|(_, _, rect)| rect.contains(pos))
+        .map(|(_, folder_idx, _)| *folder_idx)
+}
 
 // Handle drop logic - returns move request if item was dropped on a folder
 pub fn handle_drop(
@@ -8,15 +24,59 @@ pub fn handle_drop(
     folder_rects: &[(usize, usize, egui::Rect)],
 ) -> Option<(usize, usize)> {
     let from_idx = sorted_indices_snapshot[update.from];
-    let pointer_pos = ui.input(|i| i.pointer.hover_pos());
-
-    if let Some(pos) = pointer_pos {
-        for (_sorted_pos, folder_idx, rect) in folder_rects {
-            if rect.contains(pos) && *folder_idx != from_idx {
-                return Some((from_idx, *folder_idx));
-            }
-        }
+
+    folder_at_pointer(ui, folder_rects)
+        .filter(|folder_idx| *folder_idx != from_idx)
+        .map(|folder_idx| (from_idx, folder_idx))
+}
+
+// Let the other pane know what this view is dragging
+pub fn offer_drag(ui: &egui::Ui, view_id: egui::Id, paths: Vec<PathBuf>) {
+    egui::DragAndDrop::set_payload(
+        ui.ctx(),
+        DraggedPaths {
+            source: view_id,
+            paths,
+        },
+    );
+}
+
+// Paths released over area this frame, when accept takes them
+fn take_released(
+    ui: &egui::Ui,
+    area: egui::Rect,
+    accept: impl Fn(&DraggedPaths) -> bool,
+) -> Option<Vec<PathBuf>> {
+    let (released, pos) = ui.input(|i| (i.pointer.any_released(), i.pointer.hover_pos()));
+    if !released || !pos.is_some_and(|pos| area.contains(pos)) {
+        return None;
     }
 
-    None
+    let payload =
+        egui::DragAndDrop::payload::<DraggedPaths>(ui.ctx()).filter(|payload| accept(payload))?;
+    egui::DragAndDrop::clear_payload(ui.ctx());
+    Some(payload.paths.clone())
+}
+
+// Items of another pane released over this view. Returns them with the folder they were
+// dropped on, None when they were dropped next to the folders.
+pub fn take_pane_drop(
+    ui: &egui::Ui,
+    view_id: egui::Id,
+    area: egui::Rect,
+    folder_rects: &[(usize, usize, egui::Rect)],
+) -> Option<(Vec<PathBuf>, Option<usize>)> {
+    let paths = take_released(ui, area, |payload| payload.source != view_id)?;
+    Some((paths, folder_at_pointer(ui, folder_rects)))
+}
+
+// Items of any view released over area, e.g. the sidebar
+pub fn take_drop(ui: &egui::Ui, area: egui::Rect) -> Option<Vec<PathBuf>> {
+    take_released(ui, area, |_| true)
+}
+
+// Whether items of a view are being dragged over area
+pub fn is_dragging_over(ui: &egui::Ui, area: egui::Rect) -> bool {
+    egui::DragAndDrop::has_payload_of_type::<DraggedPaths>(ui.ctx())
+        && ui.rect_contains_pointer(area)
 }
@@ -1,5 +1,175 @@
-use crate::file_system::file_tree::FileNode;
-use crate::ui::settings::Settings;
+use crate::file_system::{file_tree::FileNode, formatting};
+use crate::ui::settings::{Settings, SortKey};
+use icu_collator::{Collator, CollatorOptions, Numeric, Strength};
+use icu_normalizer::DecomposingNormalizerBorrowed;
+use icu_normalizer::properties::CanonicalCombiningClassMapBorrowed;
+use icu_provider::DataLocale;
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+// How names compare, taken from the settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Collation {
+    // Runs of digits compare by value, "file2" before "file10"
+    pub natural: bool,
+    // Accents are dropped before comparing, "Été" next to "ete"
+    pub ignore_accents: bool,
+    // Names compare by the rules of the user's language instead of by code point
+    pub locale: bool,
+}
+
+impl Collation {
+    // Collator for the user's locale set up for these options, built on first use
+    fn collator(self) -> Option<&'static Collator> {
+        static COLLATORS: [OnceLock<Option<Collator>>; 4] = [const { OnceLock::new() }; 4];
+        if !self.locale {
+            return None;
+        }
+        let index = usize::from(self.natural) * 2 + usize::from(self.ignore_accents);
+        COLLATORS[index]
+            .get_or_init(|| {
+                let mut options = CollatorOptions::new();
+                // Case only breaks ties, like without the locale
+                options.strength = Some(if self.ignore_accents {
+                    Strength::Primary
+                } else {
+                    Strength::Secondary
+                });
+                if self.natural {
+                    options.numeric = Some(Numeric::On);
+                }
+                Collator::try_new(&user_locale(), options).ok()
+            })
+            .as_ref()
+    }
+}
+
+// The locale names are collated for, as set in the environment. "de_DE.UTF-8" becomes "de-DE",
+// "C" and anything else unparsable the root locale.
+fn user_locale() -> DataLocale {
+    ["LC_ALL", "LC_COLLATE", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| {
+            let tag = value.split(['.', '@']).next().unwrap_or_default();
+            tag.replace('_', "-").parse().ok()
+        })
+        .unwrap_or_default()
+}
+
+// What a name sorts by, worked out once when its node is created
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NameKey {
+    lower: String,
+    // Lowercase without accents
+    unaccented: String,
+    // Lowercase extension, empty without one
+    extension: String,
+}
+
+impl NameKey {
+    pub fn new(name: &str) -> Self {
+        let lower = name.to_lowercase();
+        let unaccented = if lower.is_ascii() {
+            lower.clone()
+        } else {
+            let combining = CanonicalCombiningClassMapBorrowed::new();
+            DecomposingNormalizerBorrowed::new_nfd()
+                .normalize_iter(lower.chars())
+                .filter(|c| combining.get32_u8(*c as u32) == 0)
+                .collect()
+        };
+        let extension = std::path::Path::new(&lower)
+            .extension()
+            .map(|ext| ext.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self {
+            lower,
+            unaccented,
+            extension,
+        }
+    }
+
+    fn folded(&self, collation: Collation) -> &str {
+        if collation.ignore_accents {
+            &self.unaccented
+        } else {
+            &self.lower
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+// Like comparing the strings, except that runs of digits compare by their value
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        let order = match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_digits(&mut a);
+                let y = take_digits(&mut b);
+                let (x_value, y_value) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                // Longer without leading zeros is larger, "007" after "7"
+                x_value
+                    .len()
+                    .cmp(&y_value.len())
+                    .then_with(|| x_value.cmp(y_value))
+                    .then_with(|| x.len().cmp(&y.len()))
+            }
+            (Some(x), Some(y)) => {
+                let order = x.cmp(y);
+                a.next();
+                b.next();
+                order
+            }
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+}
+
+// Names equal once folded still get an order: accents, then case, then the raw name
+pub fn compare_names(a: &FileNode, b: &FileNode, collation: Collation) -> Ordering {
+    let (a_folded, b_folded) = (a.name_key.folded(collation), b.name_key.folded(collation));
+    let folded = if let Some(collator) = collation.collator() {
+        collator.compare(&a.name, &b.name)
+    } else if collation.natural {
+        natural_cmp(a_folded, b_folded)
+    } else {
+        a_folded.cmp(b_folded)
+    };
+    folded
+        .then_with(|| a.name_key.lower.cmp(&b.name_key.lower))
+        .then_with(|| a.name.cmp(&b.name))
+}
+
+// Order two items by key, by name when they're equal for it. Items missing the metadata
+// come first.
+fn compare_by_key(a: &FileNode, b: &FileNode, key: SortKey) -> Ordering {
+    match key {
+        SortKey::Name => Ordering::Equal,
+        SortKey::Size => a.metadata.size.cmp(&b.metadata.size),
+        SortKey::Modified => a.metadata.modified.cmp(&b.metadata.modified),
+        SortKey::Created => a.metadata.created.cmp(&b.metadata.created),
+        SortKey::Type => formatting::get_file_type(&a.name, a.is_dir)
+            .cmp(formatting::get_file_type(&b.name, b.is_dir)),
+        SortKey::Extension => a.name_key.extension.cmp(&b.name_key.extension),
+    }
+}
 
 // Get sorted indices based on settings
 pub fn get_sorted_indices_for_vec(children: &[FileNode], settings: &Settings) -> Vec<usize> {
@@ -18,13 +188,19 @@ pub fn get_sorted_indices_for_vec(children: &[FileNode], settings: &Settings) ->
         }
     }
 
+    let collation = settings.collation();
+    let sort_fn = |&a: &usize, &b: &usize| {
+        let (child_a, child_b) = (&children[a], &children[b]);
+        let order = compare_by_key(child_a, child_b, settings.sort_key)
+            .then_with(|| compare_names(child_a, child_b, collation));
+        if settings.sort_descending {
+            order.reverse()
+        } else {
+            order
+        }
+    };
+
     if settings.sort_items {
-        let sort_fn = |&a: &usize, &b: &usize| {
-            children[a]
-                .name
-                .to_lowercase()
-                .cmp(&children[b].name.to_lowercase())
-        };
         folder_indices.sort_by(sort_fn);
         file_indices.sort_by(sort_fn);
     }
@@ -37,12 +213,7 @@ pub fn get_sorted_indices_for_vec(children: &[FileNode], settings: &Settings) ->
             .chain(file_indices)
             .collect::<Vec<_>>();
         if settings.sort_items {
-            all.sort_by(|&a, &b| {
-                children[a]
-                    .name
-                    .to_lowercase()
-                    .cmp(&children[b].name.to_lowercase())
-            });
+            all.sort_by(sort_fn);
         }
         all
     }
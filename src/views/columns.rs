@@ -0,0 +1,405 @@
+use crate::file_system::{file_tree::FileNode, formatting};
+use crate::ui::settings::{Settings, SortKey};
+use crate::views::common;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+const NAME_MIN_WIDTH: f32 = 120.0;
+const MIN_WIDTH: f32 = 40.0;
+const CELL_PADDING: f32 = 6.0;
+const HEADER_HEIGHT: f32 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Name,
+    Created,
+    Size,
+    Modified,
+    Accessed,
+    Type,
+    Permissions,
+    Owner,
+    Group,
+    ItemCount,
+    LinkTarget,
+}
+
+impl Column {
+    pub const ALL: [Column; 11] = [
+        Column::Name,
+        Column::Created,
+        Column::Size,
+        Column::Modified,
+        Column::Accessed,
+        Column::Type,
+        Column::Permissions,
+        Column::Owner,
+        Column::Group,
+        Column::ItemCount,
+        Column::LinkTarget,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::Created => "Creation Date",
+            Column::Size => "Size",
+            Column::Modified => "Modified",
+            Column::Accessed => "Accessed",
+            Column::Type => "Type",
+            Column::Permissions => "Permissions",
+            Column::Owner => "Owner",
+            Column::Group => "Group",
+            Column::ItemCount => "Items",
+            Column::LinkTarget => "Link Target",
+        }
+    }
+
+    fn default_width(&self) -> f32 {
+        match self {
+            Column::Name => NAME_MIN_WIDTH,
+            Column::Created | Column::Modified | Column::Accessed => 180.0,
+            Column::Size | Column::Permissions => 110.0,
+            Column::Type => 120.0,
+            Column::Owner | Column::Group => 90.0,
+            Column::ItemCount => 70.0,
+            Column::LinkTarget => 200.0,
+        }
+    }
+
+    // What clicking the column's title sorts by
+    fn sort_key(&self) -> Option<SortKey> {
+        match self {
+            Column::Name => Some(SortKey::Name),
+            Column::Created => Some(SortKey::Created),
+            Column::Size => Some(SortKey::Size),
+            Column::Modified => Some(SortKey::Modified),
+            Column::Type => Some(SortKey::Type),
+            _ => None,
+        }
+    }
+
+    // Numbers line up on the right
+    fn is_numeric(&self) -> bool {
+        matches!(self, Column::Size | Column::ItemCount)
+    }
+
+    // What the column shows for an item. The name column draws its own content.
+    fn text(&self, node: &FileNode) -> String {
+        let meta = &node.metadata;
+        match self {
+            Column::Name => node.name.clone(),
+            Column::Created => formatting::format_date(meta.created),
+            Column::Modified => formatting::format_date(meta.modified),
+            Column::Accessed => formatting::format_date(meta.accessed),
+            Column::Size if node.is_dir => String::new(),
+            Column::Size => formatting::format_optional_size(meta.size),
+            Column::Type => formatting::get_file_type(&node.name, node.is_dir).to_string(),
+            Column::Permissions => formatting::format_permissions(meta.permissions),
+            Column::Owner => meta
+                .owner
+                .clone()
+                .unwrap_or_else(|| formatting::UNAVAILABLE.to_string()),
+            Column::Group => meta
+                .group
+                .clone()
+                .unwrap_or_else(|| formatting::UNAVAILABLE.to_string()),
+            Column::ItemCount => meta
+                .item_count
+                .map(|count| count.to_string())
+                .unwrap_or_default(),
+            Column::LinkTarget => meta
+                .symlink_target
+                .as_ref()
+                .map(|target| target.display().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// A column of the list view as the user arranged it. The name column's width is whatever
+// the others leave.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListColumn {
+    pub column: Column,
+    pub width: f32,
+    pub visible: bool,
+}
+
+impl ListColumn {
+    fn new(column: Column, visible: bool) -> Self {
+        Self {
+            column,
+            width: column.default_width(),
+            visible,
+        }
+    }
+}
+
+// Name, creation date and size, the other columns hidden after them
+pub fn default_columns() -> Vec<ListColumn> {
+    Column::ALL
+        .into_iter()
+        .map(|column| {
+            let visible = matches!(column, Column::Name | Column::Created | Column::Size);
+            ListColumn::new(column, visible)
+        })
+        .collect()
+}
+
+// The entry of a column, added hidden when a saved layout doesn't have it yet
+fn entry(columns: &mut Vec<ListColumn>, column: Column) -> &mut ListColumn {
+    let index = match columns.iter().position(|c| c.column == column) {
+        Some(index) => index,
+        None => {
+            columns.push(ListColumn::new(column, false));
+            columns.len() - 1
+        }
+    };
+    &mut columns[index]
+}
+
+// The visible columns in order, each once and the name column always among them
+fn shown(columns: &[ListColumn]) -> Vec<ListColumn> {
+    let mut shown: Vec<ListColumn> = Vec::new();
+    for column in columns.iter().filter(|c| c.visible) {
+        if !shown.iter().any(|c| c.column == column.column) {
+            shown.push(column.clone());
+        }
+    }
+    if !shown.iter().any(|c| c.column == Column::Name) {
+        shown.insert(0, ListColumn::new(Column::Name, true));
+    }
+    shown
+}
+
+pub fn is_shown(columns: &[ListColumn], column: Column) -> bool {
+    shown(columns).iter().any(|c| c.column == column)
+}
+
+// Where the shown columns sit across a row
+pub fn layout(row: egui::Rect, shown: &[ListColumn]) -> Vec<(Column, egui::Rect)> {
+    let others: f32 = shown
+        .iter()
+        .filter(|c| c.column != Column::Name)
+        .map(|c| c.width)
+        .sum();
+    let name_width = (row.width() - others).max(NAME_MIN_WIDTH);
+
+    let mut x = row.left();
+    shown
+        .iter()
+        .map(|c| {
+            let width = if c.column == Column::Name {
+                name_width
+            } else {
+                c.width
+            };
+            let rect = egui::Rect::from_x_y_ranges(x..=x + width, row.y_range());
+            x += width;
+            (c.column, rect)
+        })
+        .collect()
+}
+
+// The text of a cell other than the name, cut off at the cell's edge
+pub fn paint_cell(
+    ui: &egui::Ui,
+    column: Column,
+    node: &FileNode,
+    cell: egui::Rect,
+    color: egui::Color32,
+) {
+    let inner = cell.shrink2(egui::vec2(CELL_PADDING, 0.0));
+    let (pos, align, font) = if column.is_numeric() {
+        (
+            inner.right_center(),
+            egui::Align2::RIGHT_CENTER,
+            egui::FontId::monospace(16.0),
+        )
+    } else {
+        (
+            inner.left_center(),
+            egui::Align2::LEFT_CENTER,
+            egui::FontId::proportional(16.0),
+        )
+    };
+    ui.painter()
+        .with_clip_rect(inner.intersect(ui.clip_rect()))
+        .text(pos, align, column.text(node), font, color);
+}
+
+// A header drag in progress, remembered between frames
+#[derive(Clone, Copy)]
+enum HeaderDrag {
+    Resize { column: Column, width: f32 },
+    Move(Column),
+}
+
+// Checkboxes for the columns to show
+fn columns_menu(ui: &mut egui::Ui, columns: &[ListColumn]) -> Option<Vec<ListColumn>> {
+    let mut change = None;
+    for column in Column::ALL.into_iter().filter(|c| *c != Column::Name) {
+        let mut visible = columns.iter().any(|c| c.column == column && c.visible);
+        if ui.checkbox(&mut visible, column.title()).changed() {
+            let mut columns = columns.to_vec();
+            entry(&mut columns, column).visible = visible;
+            change = Some(columns);
+        }
+    }
+    ui.separator();
+    if ui.button("Reset Columns").clicked() {
+        change = Some(default_columns());
+        ui.close();
+    }
+    change
+}
+
+// Move a column next to another, after it when dropped on its right half
+fn move_column(
+    columns: &[ListColumn],
+    column: Column,
+    target: Column,
+    after: bool,
+) -> Vec<ListColumn> {
+    let mut columns = columns.to_vec();
+    entry(&mut columns, target);
+    let from = columns.iter().position(|c| c.column == column);
+    if let Some(moving) = from.map(|from| columns.remove(from)) {
+        let to = columns
+            .iter()
+            .position(|c| c.column == target)
+            .map_or(columns.len(), |to| if after { to + 1 } else { to });
+        columns.insert(to, moving);
+    }
+    columns
+}
+
+// The header of the list view. Clicking a title sorts by that column, dragging a title moves
+// the column, dragging the edge after it resizes it and right-clicking chooses the columns.
+// Returns the columns to draw the rows with, and the new layout once a change is done.
+pub fn show_header(
+    ui: &mut egui::Ui,
+    settings: &Settings,
+    sort_request: &mut Option<(SortKey, bool)>,
+) -> (Vec<ListColumn>, Option<Vec<ListColumn>>) {
+    let id = ui.id().with("list_header");
+    let mut drag = ui.data(|data| data.get_temp::<HeaderDrag>(id));
+    let mut change = None;
+
+    let mut shown = shown(&settings.list_columns);
+    if let Some(HeaderDrag::Resize { column, width }) = drag
+        && let Some(resized) = shown.iter_mut().find(|c| c.column == column)
+    {
+        resized.width = width;
+    }
+
+    let (rect, background) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width() - 3.0, HEADER_HEIGHT),
+        egui::Sense::click(),
+    );
+    background.context_menu(|ui| {
+        if let Some(columns) = columns_menu(ui, &settings.list_columns) {
+            change = Some(columns);
+        }
+    });
+
+    let cells = layout(rect, &shown);
+    let text_color = ui.visuals().strong_text_color();
+    let line = ui.visuals().widgets.noninteractive.bg_stroke;
+
+    for (&(column, cell), width) in cells.iter().zip(shown.iter().map(|c| c.width)) {
+        let title = ui.interact(cell, id.with(column), egui::Sense::click_and_drag());
+        let sort_key = column.sort_key();
+
+        let mut text = column.title().to_string();
+        if settings.sort_items && sort_key == Some(settings.sort_key) {
+            let arrow = if settings.sort_descending {
+                egui_phosphor::regular::CARET_DOWN
+            } else {
+                egui_phosphor::regular::CARET_UP
+            };
+            text = format!("{} {}", text, arrow);
+        }
+        let inner = cell.shrink2(egui::vec2(CELL_PADDING, 0.0));
+        ui.painter().with_clip_rect(inner).text(
+            inner.left_center(),
+            egui::Align2::LEFT_CENTER,
+            text,
+            egui::FontId::proportional(14.0),
+            text_color,
+        );
+        ui.painter()
+            .vline(cell.right(), cell.y_range().shrink(4.0), line);
+
+        if title.hovered() && sort_key.is_some() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        }
+        if title.clicked()
+            && let Some(key) = sort_key
+        {
+            *sort_request = Some(common::toggle_sort(settings, key));
+        }
+        if title.drag_started() {
+            drag = Some(HeaderDrag::Move(column));
+        }
+        title.context_menu(|ui| {
+            if let Some(columns) = columns_menu(ui, &settings.list_columns) {
+                change = Some(columns);
+            }
+        });
+
+        // Added last so the edge wins over the title of the next column
+        if column != Column::Name {
+            let edge = egui::Rect::from_x_y_ranges(
+                cell.right() - 3.0..=cell.right() + 3.0,
+                cell.y_range(),
+            );
+            let handle = ui
+                .interact(edge, id.with((column, "resize")), egui::Sense::drag())
+                .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+            if handle.dragged() {
+                let width = (width + handle.drag_delta().x).max(MIN_WIDTH);
+                drag = Some(HeaderDrag::Resize { column, width });
+            }
+            if handle.drag_stopped() {
+                let mut columns = settings.list_columns.clone();
+                entry(&mut columns, column).width = width;
+                change = Some(columns);
+                drag = None;
+            }
+        }
+    }
+
+    // A moved column goes where it's released, a line shows where that is
+    if let Some(HeaderDrag::Move(column)) = drag {
+        let (pointer, released) = ui.input(|i| (i.pointer.hover_pos(), i.pointer.any_released()));
+        let target = pointer.and_then(|pos| {
+            cells
+                .iter()
+                .find(|(_, cell)| cell.x_range().contains(pos.x))
+                .map(|&(target, cell)| (target, cell, pos.x > cell.center().x))
+        });
+
+        if released {
+            if let Some((target, _, after)) = target
+                && target != column
+            {
+                change = Some(move_column(&settings.list_columns, column, target, after));
+            }
+            drag = None;
+        } else if let Some((_, cell, after)) = target {
+            let x = if after { cell.right() } else { cell.left() };
+            ui.painter()
+                .vline(x, rect.y_range(), ui.visuals().selection.stroke);
+        }
+    }
+
+    match drag {
+        Some(drag) => ui.data_mut(|data| data.insert_temp(id, drag)),
+        None => ui.data_mut(|data| data.remove::<HeaderDrag>(id)),
+    }
+
+    (shown, change)
+}
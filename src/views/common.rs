@@ -1,4 +1,13 @@
+use crate::file_system::{
+    file_tree::FileNode,
+    formatting,
+    operations::{self, FileAction},
+};
+use crate::ui::color_schemes::ThemeExtras;
+use crate::ui::settings::{Settings, SortKey};
+use crate::views::{RenameState, ViewState, selection::Selection};
 use eframe::egui;
+use eframe::egui::text::{CCursor, CCursorRange};
 
 // Common visual feedback for drag and drop
 pub fn draw_item_feedback(
@@ -6,13 +15,17 @@ pub fn draw_item_feedback(
     rect: egui::Rect,
     is_dragged: bool,
     is_drop_target: bool,
+    is_selected: bool,
     is_hovered: bool,
     is_drag_active: bool,
 ) {
+    let extras = ThemeExtras::get(ui.ctx());
+    let radius = extras.item_radius;
+
     if is_dragged {
         ui.painter().rect_filled(
             rect,
-            5.0,
+            radius,
             ui.style()
                 .visuals
                 .widgets
@@ -21,19 +34,274 @@ pub fn draw_item_feedback(
                 .gamma_multiply(0.3),
         );
     } else if is_drop_target {
-        ui.painter().rect_filled(
-            rect,
-            5.0,
-            egui::Color32::from_rgba_premultiplied(100, 200, 255, 50),
-        );
+        ui.painter()
+            .rect_filled(rect, radius, extras.drop_target.gamma_multiply(0.2));
         ui.painter().rect_stroke(
             rect,
-            5.0,
-            egui::Stroke::new(2.0, egui::Color32::from_rgb(100, 200, 255)),
+            radius,
+            egui::Stroke::new(2.0, extras.drop_target),
             egui::StrokeKind::Outside,
         );
+    } else if is_selected {
+        ui.painter().rect_filled(
+            rect,
+            radius,
+            ui.style().visuals.selection.bg_fill.gamma_multiply(0.4),
+        );
     } else if is_hovered && !is_drag_active {
-        ui.painter()
-            .rect_filled(rect, 5.0, ui.style().visuals.widgets.hovered.weak_bg_fill);
+        ui.painter().rect_filled(
+            rect,
+            radius,
+            ui.style().visuals.widgets.hovered.weak_bg_fill,
+        );
     }
 }
+
+// Vertical scroll area for the views. Drags on empty space draw a selection band, so they
+// don't scroll.
+pub fn scroll_area() -> egui::ScrollArea {
+    egui::ScrollArea::vertical().scroll_source(
+        egui::scroll_area::ScrollSource::SCROLL_BAR | egui::scroll_area::ScrollSource::MOUSE_WHEEL,
+    )
+}
+
+// The selected items among the visible ones, in display order
+pub fn selected_nodes<'a>(visible: &[&'a FileNode], selection: &Selection) -> Vec<&'a FileNode> {
+    visible
+        .iter()
+        .filter(|child| selection.contains(&child.path))
+        .copied()
+        .collect()
+}
+
+// What an action on an item applies to: the whole selection when the item is part of it
+pub fn action_targets<'a>(
+    visible: &[&'a FileNode],
+    selection: &Selection,
+    child: &'a FileNode,
+) -> Vec<&'a FileNode> {
+    if selection.contains(&child.path) {
+        selected_nodes(visible, selection)
+    } else {
+        vec![child]
+    }
+}
+
+// Ring around the item the keyboard moves from
+pub fn draw_focus_ring(ui: &mut egui::Ui, rect: egui::Rect) {
+    let stroke = egui::Stroke::new(1.5, ui.style().visuals.selection.stroke.color);
+    let radius = ThemeExtras::get(ui.ctx()).item_radius;
+    ui.painter()
+        .rect_stroke(rect, radius, stroke, egui::StrokeKind::Inside);
+}
+
+// Spinner shown while a folder's entries are still streaming in
+pub fn draw_loading_indicator(ui: &mut egui::Ui, node: &FileNode) {
+    if !node.loading {
+        return;
+    }
+
+    let count = node.children.as_ref().map_or(0, |c| c.len());
+    ui.horizontal(|ui| {
+        ui.spinner();
+        ui.label(egui::RichText::new(format!("Loading… {} items", count)).weak());
+    });
+}
+
+// Details shown when hovering an item, read from the cached metadata
+pub fn metadata_tooltip(ui: &mut egui::Ui, node: &FileNode) {
+    let meta = &node.metadata;
+
+    egui::Grid::new("metadata_tooltip")
+        .num_columns(2)
+        .spacing([12.0, 2.0])
+        .show(ui, |ui| {
+            let mut row = |label: &str, value: String| {
+                ui.label(egui::RichText::new(label).weak());
+                ui.label(value);
+                ui.end_row();
+            };
+
+            row("Name", node.name.clone());
+            if !node.is_dir {
+                row("Size", formatting::format_optional_size(meta.size));
+            }
+            row("Modified", formatting::format_date(meta.modified));
+            row("Changed", formatting::format_date(meta.changed));
+            row("Created", formatting::format_date(meta.created));
+            row("Accessed", formatting::format_date(meta.accessed));
+            row(
+                "Permissions",
+                formatting::format_permissions(meta.permissions),
+            );
+            row(
+                "Owner",
+                format!(
+                    "{}:{}",
+                    meta.owner.as_deref().unwrap_or(formatting::UNAVAILABLE),
+                    meta.group.as_deref().unwrap_or(formatting::UNAVAILABLE)
+                ),
+            );
+            if let Some(target) = &meta.symlink_target {
+                row("Link target", target.display().to_string());
+            }
+        });
+}
+
+// Explain why a folder shows no entries when listing it failed
+pub fn draw_load_error(ui: &mut egui::Ui, node: &FileNode) {
+    if let Some(error) = &node.load_error {
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new(egui_phosphor::regular::WARNING)
+                    .color(ui.visuals().error_fg_color),
+            );
+            ui.label(
+                egui::RichText::new(format!("Couldn't read this folder: {}", error))
+                    .color(ui.visuals().error_fg_color),
+            );
+        });
+    }
+}
+
+pub enum RenameOutcome {
+    Editing,
+    Commit,
+    Cancel,
+}
+
+// Text field replacing an item's name while it's being renamed
+pub fn rename_field(
+    ui: &mut egui::Ui,
+    rename: &mut RenameState,
+    font_size: f32,
+    width: f32,
+) -> RenameOutcome {
+    let mut output = egui::TextEdit::singleline(&mut rename.text)
+        .font(egui::FontId::proportional(font_size))
+        .desired_width(width)
+        .show(ui);
+
+    // Focus and select the stem on the first frame only
+    if let Some(select_len) = rename.select_len.take() {
+        output.response.request_focus();
+        output.state.cursor.set_char_range(Some(CCursorRange::two(
+            CCursor::new(0),
+            CCursor::new(select_len),
+        )));
+        output.state.store(ui.ctx(), output.response.id);
+        return RenameOutcome::Editing;
+    }
+
+    if output.response.changed() {
+        rename.error = operations::validate_name(&rename.path, &rename.text).err();
+    }
+    if let Some(error) = &rename.error {
+        ui.label(
+            egui::RichText::new(error)
+                .color(ui.visuals().error_fg_color)
+                .small(),
+        );
+    }
+
+    if !output.response.lost_focus() {
+        return RenameOutcome::Editing;
+    }
+
+    let (escape, enter) = ui.input(|i| {
+        (
+            i.key_pressed(egui::Key::Escape),
+            i.key_pressed(egui::Key::Enter),
+        )
+    });
+    if escape {
+        RenameOutcome::Cancel
+    } else if rename.error.is_none() {
+        // The Enter that committed shouldn't also open the renamed folder
+        ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter));
+        RenameOutcome::Commit
+    } else if enter {
+        // Keep editing so the name can be fixed
+        output.response.request_focus();
+        RenameOutcome::Editing
+    } else {
+        RenameOutcome::Cancel
+    }
+}
+
+// F2 renames the selected item, when it's the only one
+pub fn handle_rename_shortcut(ui: &egui::Ui, state: &ViewState) -> Option<FileAction> {
+    if state.rename.is_some() || ui.ctx().wants_keyboard_input() {
+        return None;
+    }
+
+    let selected = state.selection.single()?;
+    ui.input(|i| i.key_pressed(egui::Key::F2))
+        .then(|| FileAction::StartRename(selected.to_path_buf()))
+}
+
+// Draw the rename field in place of a child's name if it's the one being renamed.
+// Returns false when the child isn't being renamed and its name should be drawn as usual.
+pub fn show_rename(
+    ui: &mut egui::Ui,
+    view_state: &mut ViewState,
+    child: &FileNode,
+    font_size: f32,
+    width: f32,
+    actions: &mut Vec<FileAction>,
+) -> bool {
+    let Some(rename) = view_state
+        .rename
+        .as_mut()
+        .filter(|rename| rename.path == child.path)
+    else {
+        return false;
+    };
+
+    match rename_field(ui, rename, font_size, width) {
+        RenameOutcome::Editing => {}
+        RenameOutcome::Commit => {
+            if rename.text != child.name {
+                actions.push(FileAction::Rename {
+                    from: child.path.clone(),
+                    new_name: rename.text.clone(),
+                });
+            }
+            view_state.rename = None;
+        }
+        RenameOutcome::Cancel => view_state.rename = None,
+    }
+
+    true
+}
+
+// Sorting by the key the items already are sorted by flips the order, another key starts
+// ascending
+pub fn toggle_sort(settings: &Settings, key: SortKey) -> (SortKey, bool) {
+    let sorted_by_key = settings.sort_items && settings.sort_key == key;
+    (key, sorted_by_key && !settings.sort_descending)
+}
+
+// "Sort by" submenu of the background menu
+pub fn sort_menu(ui: &mut egui::Ui, settings: &Settings) -> Option<(SortKey, bool)> {
+    let mut request = None;
+    ui.menu_button("Sort by", |ui| {
+        for key in SortKey::ALL {
+            let selected = settings.sort_items && settings.sort_key == key;
+            if ui.radio(selected, key.label()).clicked() {
+                request = Some((key, settings.sort_descending));
+                ui.close();
+            }
+        }
+        ui.separator();
+        if ui.radio(!settings.sort_descending, "Ascending").clicked() {
+            request = Some((settings.sort_key, false));
+            ui.close();
+        }
+        if ui.radio(settings.sort_descending, "Descending").clicked() {
+            request = Some((settings.sort_key, true));
+            ui.close();
+        }
+    });
+    request
+}
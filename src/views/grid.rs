@@ -1,46 +1,84 @@
-use crate::file_system::{file_tree::FileNode, formatting::get_file_icon, operations};
+use crate::file_system::{
+    file_tree::FileNode,
+    formatting::get_file_icon,
+    operations::{self, Clipboard, FileAction},
+};
 use crate::ui::settings::Settings;
 use crate::utils::{drag_drop, sorting};
-use crate::views::common;
+use crate::views::{ViewResponse, ViewState, common, keyboard, selection};
 use eframe::egui;
+use std::path::{Path, PathBuf};
+
+// Width of an item's icon and name
+const ITEM_WIDTH: f32 = 120.0;
 
 pub fn render_grid_view(
     ui: &mut egui::Ui,
-    node: &mut FileNode,
+    node: &FileNode,
+    view_state: &mut ViewState,
     settings: &Settings,
-) -> Option<std::path::PathBuf> {
+    clipboard: Option<&Clipboard>,
+) -> ViewResponse {
+    let view_id = ui.id();
     let mut nav_request = None;
+    let mut new_tab_request = None;
+    let mut sort_request = None;
     let mut move_request: Option<(usize, usize)> = None;
-    let mut delete_request: Option<usize> = None;
+    let mut pane_drop = None;
+    let mut actions: Vec<FileAction> = Vec::new();
 
     let confirm_delete_id = ui.id().with("confirm_delete");
 
-    node.ensure_children_loaded();
-    let children = node.children.as_mut().unwrap();
+    common::draw_loading_indicator(ui, node);
+    common::draw_load_error(ui, node);
+
+    let Some(children) = node.children.as_ref() else {
+        return ViewResponse::default();
+    };
 
-    let original_children = children.clone();
     let mut sorted_indices = sorting::get_sorted_indices_for_vec(children, settings);
+    let visible: Vec<&FileNode> = sorted_indices.iter().map(|&idx| &children[idx]).collect();
+    let order: Vec<&Path> = visible.iter().map(|child| child.path.as_path()).collect();
+    view_state.selection.retain_visible(&order);
 
-    egui::ScrollArea::vertical().show(ui, |ui| {
+    // Clicks and drags that miss every item land on the background
+    let background = ui.interact(
+        ui.available_rect_before_wrap(),
+        ui.id().with("background"),
+        egui::Sense::click_and_drag(),
+    );
+    let mut item_rects: Vec<(&Path, egui::Rect)> = Vec::new();
+    let mut grid_columns = 1;
+    let mut page_size = 1;
+
+    common::scroll_area().show(ui, |ui| {
         ui.horizontal_wrapped(|ui| {
             ui.spacing_mut().item_spacing = egui::vec2(20.0, 20.0);
 
             let mut dragged_idx: Option<usize> = None;
             let mut folder_rects: Vec<(usize, usize, egui::Rect)> = Vec::new();
 
-            let av_width = ui.available_width() - ui.spacing().item_spacing.x;
-            let columns = (av_width / 100.0).ceil() as usize;
-            let width = av_width / columns as f32;
-            let size = egui::Vec2::new(width, width) + ui.spacing().item_spacing;
+            // As many cells as fit with room for an item, widened so they fill the row exactly.
+            // Each one takes its width and the spacing after it.
+            let spacing = ui.spacing().item_spacing.x;
+            let av_width = ui.available_width() - spacing;
+            let columns = ((av_width + spacing) / (ITEM_WIDTH + spacing)).max(1.0) as usize;
+            let width = (av_width + spacing) / columns as f32 - spacing;
+            let size = egui::Vec2::new(width, width);
+            grid_columns = columns;
+            let row_height = size.y + ui.spacing().item_spacing.y;
+            page_size = columns * (ui.clip_rect().height() / row_height) as usize;
 
             let sorted_indices_snapshot = sorted_indices.clone();
 
-            let response = egui_dnd::dnd(ui, "file_explorer_dnd").show_vec_sized(
+            let response = egui_dnd::dnd(ui, view_id.with("file_explorer_dnd")).show_vec_sized(
                 &mut sorted_indices,
                 size,
                 |ui, &mut child_idx, handle, state| {
                     let child = &children[child_idx];
                     let is_folder = child.is_dir;
+                    let is_selected = view_state.selection.contains(&child.path);
+                    let is_cut = clipboard.is_some_and(|c| c.is_cut(&child.path));
                     let icon = get_file_icon(&child.name, child.is_dir);
 
                     if state.dragged {
@@ -49,8 +87,12 @@ pub fn render_grid_view(
 
                     ui.vertical(|ui| {
                         handle.ui(ui, |ui| {
-                            let (rect, resp) = ui
-                                .allocate_exact_size(egui::vec2(120.0, 80.0), egui::Sense::click());
+                            let (rect, resp) = ui.allocate_exact_size(
+                                egui::vec2(ITEM_WIDTH, 80.0),
+                                egui::Sense::click(),
+                            );
+                            let resp = resp.on_hover_ui(|ui| common::metadata_tooltip(ui, child));
+                            item_rects.push((&child.path, rect));
 
                             if is_folder {
                                 let original_idx = sorted_indices_snapshot[state.index];
@@ -63,18 +105,26 @@ pub fn render_grid_view(
                                 && is_drag_active
                                 && !state.dragged
                                 && Some(state.index) != dragged_idx
-                                && pointer_pos.map_or(false, |pos| rect.contains(pos));
+                                && pointer_pos.is_some_and(|pos| rect.contains(pos));
 
                             common::draw_item_feedback(
                                 ui,
                                 rect,
                                 state.dragged,
                                 is_drop_target,
+                                is_selected,
                                 resp.hovered(),
                                 is_drag_active,
                             );
 
-                            let icon_color = if state.dragged {
+                            if view_state.selection.focus() == Some(child.path.as_path()) {
+                                common::draw_focus_ring(ui, rect);
+                                if view_state.selection.take_scroll_to_focus() {
+                                    ui.scroll_to_rect(rect, None);
+                                }
+                            }
+
+                            let icon_color = if state.dragged || is_cut {
                                 ui.style().visuals.text_color().gamma_multiply(0.5)
                             } else {
                                 ui.style().visuals.text_color()
@@ -90,26 +140,57 @@ pub fn render_grid_view(
                             );
 
                             let name_pos = rect.center() + egui::vec2(0.0, 20.0);
-                            ui.painter().text(
-                                name_pos,
-                                egui::Align2::CENTER_TOP,
-                                &child.name,
-                                egui::FontId::proportional(14.0),
-                                icon_color,
+                            let name_rect = egui::Rect::from_min_max(
+                                egui::pos2(rect.left(), name_pos.y),
+                                rect.right_bottom(),
                             );
+                            let is_renaming = ui
+                                .scope_builder(egui::UiBuilder::new().max_rect(name_rect), |ui| {
+                                    common::show_rename(
+                                        ui,
+                                        view_state,
+                                        child,
+                                        14.0,
+                                        rect.width(),
+                                        &mut actions,
+                                    )
+                                })
+                                .inner;
+                            if !is_renaming {
+                                ui.painter().text(
+                                    name_pos,
+                                    egui::Align2::CENTER_TOP,
+                                    &child.name,
+                                    egui::FontId::proportional(14.0),
+                                    icon_color,
+                                );
+                            }
+
+                            if resp.clicked() {
+                                let modifiers = ui.input(|i| i.modifiers);
+                                view_state.selection.click(&child.path, modifiers, &order);
+                            }
+                            // Right-clicking outside the selection acts on that item alone
+                            if resp.secondary_clicked()
+                                && !view_state.selection.contains(&child.path)
+                            {
+                                view_state.selection.select_only(&child.path);
+                            }
 
                             if resp.double_clicked() && is_folder && !is_drag_active {
                                 nav_request = Some(child.path.clone());
                             }
+                            if resp.middle_clicked() && is_folder {
+                                new_tab_request = Some(child.path.clone());
+                            }
 
                             resp.context_menu(|ui| {
-                                if let Some(idx) = operations::show_context_menu(
-                                    ui,
-                                    child,
-                                    child_idx,
-                                    confirm_delete_id,
-                                ) {
-                                    delete_request = Some(idx);
+                                let targets =
+                                    common::action_targets(&visible, &view_state.selection, child);
+                                if let Some(action) =
+                                    operations::show_context_menu(ui, &targets, confirm_delete_id)
+                                {
+                                    actions.push(action);
                                 }
                             });
                         });
@@ -118,30 +199,103 @@ pub fn render_grid_view(
             );
 
             if let Some(update) = response.final_update() {
-                if let Some(move_req) =
-                    drag_drop::handle_drop(ui, &update, &sorted_indices_snapshot, &folder_rects)
-                {
-                    move_request = Some(move_req);
-                } else {
-                    *children = original_children.clone();
-                }
+                move_request =
+                    drag_drop::handle_drop(ui, &update, &sorted_indices_snapshot, &folder_rects);
+            }
+
+            // Dragged items can be dropped on the other pane too
+            if let Some(idx) = dragged_idx {
+                let paths = common::action_targets(&visible, &view_state.selection, visible[idx])
+                    .into_iter()
+                    .map(|child| child.path.clone())
+                    .collect();
+                drag_drop::offer_drag(ui, view_id, paths);
             }
+            pane_drop = drag_drop::take_pane_drop(ui, view_id, background.rect, &folder_rects);
         });
     });
 
-    // Handle delete confirmation modal
-    if let Some(idx) = operations::show_delete_confirmation_modal(ui, confirm_delete_id) {
-        delete_request = Some(idx);
+    let modifiers = ui.input(|i| i.modifiers);
+    if background.clicked() && !modifiers.command && !modifiers.shift {
+        view_state.selection.clear();
     }
+    selection::handle_rubber_band(ui, &background, &mut view_state.selection, &item_rects);
+    background.context_menu(|ui| {
+        if let Some(action) = operations::show_background_context_menu(ui, node, clipboard) {
+            actions.push(action);
+        }
+        selection::show_selection_menu(ui, &mut view_state.selection, &order);
+        ui.separator();
+        if let Some(request) = common::sort_menu(ui, settings) {
+            sort_request = Some(request);
+        }
+    });
 
-    // Execute operations
-    if let Some(idx) = delete_request {
-        operations::execute_delete(node, idx);
+    // The inactive pane leaves the keyboard to the active one
+    if !view_state.inactive {
+        let selected = common::selected_nodes(&visible, &view_state.selection);
+        let selected_paths: Vec<PathBuf> =
+            selected.iter().map(|child| child.path.clone()).collect();
+        if let Some(action) =
+            operations::handle_clipboard_shortcuts(ui, &selected_paths, &node.path)
+        {
+            actions.push(action);
+        }
+        if let Some(action) = common::handle_rename_shortcut(ui, view_state) {
+            actions.push(action);
+        }
+        if let Some(action) = operations::handle_delete_shortcuts(ui, &selected, confirm_delete_id)
+        {
+            actions.push(action);
+        }
+        selection::handle_selection_shortcuts(ui, &mut view_state.selection, &order);
+        if let Some(path) = keyboard::handle_navigation_keys(
+            ui,
+            view_state,
+            node,
+            &visible,
+            grid_columns,
+            page_size,
+        ) {
+            nav_request = Some(path);
+        }
     }
 
+    // Handle delete confirmation modal
+    if let Some(paths) = operations::show_delete_confirmation_modal(ui, confirm_delete_id) {
+        actions.push(FileAction::Delete(paths));
+    }
+
+    // Dragging a selected item moves the whole selection
     if let Some((from_idx, target_folder_idx)) = move_request {
-        operations::execute_move(node, from_idx, target_folder_idx, original_children);
+        let to_dir = &children[target_folder_idx].path;
+        let paths = common::action_targets(&visible, &view_state.selection, &children[from_idx])
+            .into_iter()
+            .map(|child| child.path.clone())
+            .filter(|path| path != to_dir)
+            .collect();
+        actions.push(FileAction::Move {
+            paths,
+            to_dir: to_dir.clone(),
+        });
+    }
+
+    // Items dragged over from the other pane move into the folder they were dropped on, or
+    // into the shown folder
+    if let Some((paths, folder_idx)) = pane_drop {
+        let to_dir = folder_idx.map_or(&node.path, |idx| &children[idx].path);
+        let paths = paths.into_iter().filter(|path| path != to_dir).collect();
+        actions.push(FileAction::Move {
+            paths,
+            to_dir: to_dir.clone(),
+        });
     }
 
-    nav_request
+    ViewResponse {
+        nav_request,
+        new_tab_request,
+        sort_request,
+        columns_request: None,
+        actions,
+    }
 }
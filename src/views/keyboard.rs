@@ -0,0 +1,81 @@
+use crate::file_system::file_tree::FileNode;
+use crate::views::ViewState;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+const NAVIGATION_KEYS: [egui::Key; 10] = [
+    egui::Key::ArrowUp,
+    egui::Key::ArrowDown,
+    egui::Key::ArrowLeft,
+    egui::Key::ArrowRight,
+    egui::Key::Home,
+    egui::Key::End,
+    egui::Key::PageUp,
+    egui::Key::PageDown,
+    egui::Key::Enter,
+    egui::Key::Backspace,
+];
+
+// Move the focus through the visible items with the arrow keys, Home/End and PageUp/PageDown.
+// The items are laid out in rows of `columns`, one for the list. Returns the folder to open:
+// the focused one on Enter, the parent on Backspace or Alt+Up.
+pub fn handle_navigation_keys(
+    ui: &egui::Ui,
+    view_state: &mut ViewState,
+    node: &FileNode,
+    visible: &[&FileNode],
+    columns: usize,
+    page_size: usize,
+) -> Option<PathBuf> {
+    if view_state.rename.is_some() || ui.ctx().wants_keyboard_input() {
+        return None;
+    }
+
+    let (key, modifiers) = ui.input(|i| {
+        let key = NAVIGATION_KEYS.into_iter().find(|key| i.key_pressed(*key));
+        (key, i.modifiers)
+    });
+    let key = key?;
+
+    match key {
+        egui::Key::Backspace => return node.path.parent().map(Path::to_path_buf),
+        egui::Key::ArrowUp if modifiers.alt => return node.path.parent().map(Path::to_path_buf),
+        egui::Key::Enter => {
+            let focus = view_state.selection.focus()?;
+            let focused = visible.iter().find(|child| child.path == focus)?;
+            return focused.is_dir.then(|| focused.path.clone());
+        }
+        _ => {}
+    }
+
+    let last = visible.len().checked_sub(1)?;
+    let columns = columns.max(1);
+    let current = view_state
+        .selection
+        .focus()
+        .and_then(|focus| visible.iter().position(|child| child.path == focus));
+
+    let target = match (key, current) {
+        (egui::Key::Home, _) => 0,
+        (egui::Key::End, _) => last,
+        // Nothing focused yet, start at the top
+        (_, None) => 0,
+        (egui::Key::ArrowUp, Some(current)) => current.saturating_sub(columns),
+        (egui::Key::ArrowDown, Some(current)) => (current + columns).min(last),
+        // Left and right only move within rows of the grid
+        (egui::Key::ArrowLeft | egui::Key::ArrowRight, Some(_)) if columns == 1 => return None,
+        (egui::Key::ArrowLeft, Some(current)) => current.saturating_sub(1),
+        (egui::Key::ArrowRight, Some(current)) => (current + 1).min(last),
+        (egui::Key::PageUp, Some(current)) => current.saturating_sub(page_size.max(1)),
+        (egui::Key::PageDown, Some(current)) => (current + page_size.max(1)).min(last),
+        _ => return None,
+    };
+
+    let order: Vec<&Path> = visible.iter().map(|child| child.path.as_path()).collect();
+    view_state
+        .selection
+        .move_focus(order[target], modifiers, &order);
+    ui.ctx().request_repaint();
+
+    None
+}
@@ -1,67 +1,89 @@
-use crate::file_system::{file_tree::FileNode, formatting, operations};
+use crate::file_system::{
+    file_tree::FileNode,
+    formatting,
+    operations::{self, Clipboard, FileAction},
+};
 use crate::ui::settings::Settings;
 use crate::utils::{drag_drop, sorting};
-use crate::views::common;
+use crate::views::{
+    ViewResponse, ViewState,
+    columns::{self, Column},
+    common, keyboard, selection,
+};
 use eframe::egui;
+use std::path::{Path, PathBuf};
 
 pub fn render_list_view(
     ui: &mut egui::Ui,
-    node: &mut FileNode,
+    node: &FileNode,
+    view_state: &mut ViewState,
     settings: &Settings,
-) -> Option<std::path::PathBuf> {
-    egui::TopBottomPanel::top("placeholder").show_inside(ui, |ui| {
-        ui.style_mut().visuals.widgets.inactive.weak_bg_fill = ui.visuals().faint_bg_color;
-        ui.add_space(4.0);
-        ui.horizontal(|ui| {
-            ui.label(egui::RichText::new("Name").strong().size(14.0));
-            ui.add_space(280.0);
-            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                ui.add_space(35.0);
-                ui.allocate_ui_with_layout(
-                    egui::vec2(100.0, 20.0),
-                    egui::Layout::right_to_left(egui::Align::Center),
-                    |ui| {
-                        ui.label(egui::RichText::new("Size").strong().size(14.0));
-                    },
-                );
-                ui.add_space(35.0);
-                ui.separator();
-                ui.add_space(30.0);
-                ui.label(egui::RichText::new("Creation Date").strong().size(14.0));
-                ui.add_space(35.0);
-                ui.separator();
-            });
-        });
-        ui.add_space(4.0);
-    });
+    clipboard: Option<&Clipboard>,
+) -> ViewResponse {
+    let view_id = ui.id();
+    let mut sort_request = None;
+
+    // The header stays put while the rows scroll. It has no side margins so the columns line
+    // up with the rows.
+    let (shown_columns, columns_request) = egui::TopBottomPanel::top(view_id.with("header"))
+        .frame(egui::Frame::NONE.inner_margin(egui::Margin::symmetric(0, 4)))
+        .show_inside(ui, |ui| {
+            columns::show_header(ui, settings, &mut sort_request)
+        })
+        .inner;
 
     let mut nav_request = None;
+    let mut new_tab_request = None;
     let mut move_request: Option<(usize, usize)> = None;
-    let mut delete_request: Option<usize> = None;
+    let mut pane_drop = None;
+    let mut actions: Vec<FileAction> = Vec::new();
 
     let confirm_delete_id = ui.id().with("confirm_delete");
 
-    node.ensure_children_loaded();
-    let children = node.children.as_mut().unwrap();
+    common::draw_loading_indicator(ui, node);
+    common::draw_load_error(ui, node);
+
+    let Some(children) = node.children.as_ref() else {
+        return ViewResponse {
+            sort_request,
+            columns_request,
+            ..Default::default()
+        };
+    };
 
-    let original_children = children.clone();
     let mut sorted_indices = sorting::get_sorted_indices_for_vec(children, settings);
+    let visible: Vec<&FileNode> = sorted_indices.iter().map(|&idx| &children[idx]).collect();
+    let order: Vec<&Path> = visible.iter().map(|child| child.path.as_path()).collect();
+    view_state.selection.retain_visible(&order);
 
-    egui::ScrollArea::vertical()
+    // Clicks and drags that miss every item land on the background
+    let background = ui.interact(
+        ui.available_rect_before_wrap(),
+        ui.id().with("background"),
+        egui::Sense::click_and_drag(),
+    );
+    let mut item_rects: Vec<(&Path, egui::Rect)> = Vec::new();
+    let mut page_size = 1;
+
+    common::scroll_area()
         .max_width(ui.available_width())
         .show(ui, |ui| {
             ui.spacing_mut().item_spacing = egui::vec2(32.0, 16.0);
+            let row_height = 30.0 + ui.spacing().item_spacing.y;
+            page_size = (ui.clip_rect().height() / row_height) as usize;
 
             let mut dragged_idx: Option<usize> = None;
             let mut folder_rects: Vec<(usize, usize, egui::Rect)> = Vec::new();
 
             let sorted_indices_snapshot = sorted_indices.clone();
 
-            let response = egui_dnd::dnd(ui, "file_explorer_dnd").show_vec(
+            let response = egui_dnd::dnd(ui, view_id.with("file_explorer_dnd")).show_vec(
                 &mut sorted_indices,
                 |ui, &mut child_idx, handle, state| {
                     let child = &children[child_idx];
                     let is_folder = child.is_dir;
+                    let is_selected = view_state.selection.contains(&child.path);
+                    let is_cut = clipboard.is_some_and(|c| c.is_cut(&child.path));
 
                     if state.dragged {
                         dragged_idx = Some(state.index);
@@ -73,6 +95,8 @@ pub fn render_list_view(
                                 egui::vec2(ui.available_width() - 3.0, 30.0),
                                 egui::Sense::click(),
                             );
+                            let resp = resp.on_hover_ui(|ui| common::metadata_tooltip(ui, child));
+                            item_rects.push((&child.path, rect));
 
                             if is_folder {
                                 let original_idx = sorted_indices_snapshot[state.index];
@@ -85,103 +109,92 @@ pub fn render_list_view(
                                 && is_drag_active
                                 && !state.dragged
                                 && Some(state.index) != dragged_idx
-                                && pointer_pos.map_or(false, |pos| rect.contains(pos));
+                                && pointer_pos.is_some_and(|pos| rect.contains(pos));
 
                             common::draw_item_feedback(
                                 ui,
                                 rect,
                                 state.dragged,
                                 is_drop_target,
+                                is_selected,
                                 resp.hovered(),
                                 is_drag_active,
                             );
 
-                            ui.scope_builder(egui::UiBuilder::new().max_rect(rect), |ui| {
-                                ui.horizontal(|ui| {
-                                    let icon = formatting::get_file_icon(&child.name, child.is_dir);
-                                    let icon_color = if state.dragged {
-                                        ui.style().visuals.text_color().gamma_multiply(0.5)
-                                    } else {
-                                        ui.style().visuals.text_color()
-                                    };
-
-                                    ui.label(
-                                        egui::RichText::new(icon).color(icon_color).size(24.0),
-                                    );
-                                    ui.label(
-                                        egui::RichText::new(&child.name)
-                                            .color(icon_color)
-                                            .size(16.0),
-                                    );
-
-                                    ui.with_layout(
-                                        egui::Layout::right_to_left(egui::Align::Center),
-                                        |ui| {
-                                            if !child.is_dir {
-                                                #[cfg(target_os = "linux")]
-                                                use std::os::unix::fs::MetadataExt;
-                                                #[cfg(target_os = "windows")]
-                                                use std::os::windows::fs::MetadataExt;
-
-                                                let meta = std::fs::metadata(&child.path).unwrap();
-                                                #[cfg(target_os = "linux")]
-                                                let size = meta.size();
-                                                #[cfg(target_os = "windows")]
-                                                let size = meta.file_size();
-
-                                                ui.add_sized(
-                                                    [100.0, 20.0],
-                                                    egui::Label::new(
-                                                        egui::RichText::new(
-                                                            formatting::format_file_size(size),
-                                                        )
-                                                        .color(icon_color)
-                                                        .size(16.0)
-                                                        .monospace(),
-                                                    ),
-                                                );
-                                            } else {
-                                                ui.add_sized(
-                                                    [100.0, 20.0],
-                                                    egui::Label::new(
-                                                        egui::RichText::new("")
-                                                            .size(16.0)
-                                                            .monospace(),
-                                                    ),
-                                                );
-                                            }
-
-                                            let meta = std::fs::metadata(&child.path).unwrap();
-                                            let created: std::time::SystemTime = meta
-                                                .created()
-                                                .expect("Couldn't get file creation date.");
-                                            let datetime: chrono::DateTime<chrono::Utc> =
-                                                created.into();
-                                            let formatted =
-                                                datetime.format("%Y-%m-%d %H:%M:%S").to_string();
-
-                                            ui.label(
-                                                egui::RichText::new(formatted)
-                                                    .color(icon_color)
-                                                    .size(16.0),
+                            if view_state.selection.focus() == Some(child.path.as_path()) {
+                                common::draw_focus_ring(ui, rect);
+                                if view_state.selection.take_scroll_to_focus() {
+                                    ui.scroll_to_rect(rect, None);
+                                }
+                            }
+
+                            let text_color = if state.dragged || is_cut {
+                                ui.style().visuals.text_color().gamma_multiply(0.5)
+                            } else {
+                                ui.style().visuals.text_color()
+                            };
+
+                            for (column, cell) in columns::layout(rect, &shown_columns) {
+                                if column != Column::Name {
+                                    columns::paint_cell(ui, column, child, cell, text_color);
+                                    continue;
+                                }
+
+                                let clip = cell.intersect(ui.clip_rect());
+                                ui.scope_builder(egui::UiBuilder::new().max_rect(cell), |ui| {
+                                    ui.set_clip_rect(clip);
+                                    ui.horizontal(|ui| {
+                                        let icon =
+                                            formatting::get_file_icon(&child.name, child.is_dir);
+                                        ui.label(
+                                            egui::RichText::new(icon).color(text_color).size(24.0),
+                                        );
+                                        if !common::show_rename(
+                                            ui,
+                                            view_state,
+                                            child,
+                                            16.0,
+                                            cell.width() - 40.0,
+                                            &mut actions,
+                                        ) {
+                                            ui.add(
+                                                egui::Label::new(
+                                                    egui::RichText::new(&child.name)
+                                                        .color(text_color)
+                                                        .size(16.0),
+                                                )
+                                                .truncate(),
                                             );
-                                        },
-                                    );
+                                        }
+                                    });
                                 });
-                            });
+                            }
+
+                            if resp.clicked() {
+                                let modifiers = ui.input(|i| i.modifiers);
+                                view_state.selection.click(&child.path, modifiers, &order);
+                            }
+                            // Right-clicking outside the selection acts on that item alone
+                            if resp.secondary_clicked()
+                                && !view_state.selection.contains(&child.path)
+                            {
+                                view_state.selection.select_only(&child.path);
+                            }
 
                             if resp.double_clicked() && is_folder && !is_drag_active {
                                 nav_request = Some(child.path.clone());
                             }
+                            if resp.middle_clicked() && is_folder {
+                                new_tab_request = Some(child.path.clone());
+                            }
 
                             resp.context_menu(|ui| {
-                                if let Some(idx) = operations::show_context_menu(
-                                    ui,
-                                    child,
-                                    child_idx,
-                                    confirm_delete_id,
-                                ) {
-                                    delete_request = Some(idx);
+                                let targets =
+                                    common::action_targets(&visible, &view_state.selection, child);
+                                if let Some(action) =
+                                    operations::show_context_menu(ui, &targets, confirm_delete_id)
+                                {
+                                    actions.push(action);
                                 }
                             });
                         });
@@ -190,29 +203,97 @@ pub fn render_list_view(
             );
 
             if let Some(update) = response.final_update() {
-                if let Some(move_req) =
-                    drag_drop::handle_drop(ui, &update, &sorted_indices_snapshot, &folder_rects)
-                {
-                    move_request = Some(move_req);
-                } else {
-                    *children = original_children.clone();
-                }
+                move_request =
+                    drag_drop::handle_drop(ui, &update, &sorted_indices_snapshot, &folder_rects);
+            }
+
+            // Dragged items can be dropped on the other pane too
+            if let Some(idx) = dragged_idx {
+                let paths = common::action_targets(&visible, &view_state.selection, visible[idx])
+                    .into_iter()
+                    .map(|child| child.path.clone())
+                    .collect();
+                drag_drop::offer_drag(ui, view_id, paths);
             }
+            pane_drop = drag_drop::take_pane_drop(ui, view_id, background.rect, &folder_rects);
         });
 
-    // Handle delete confirmation modal
-    if let Some(idx) = operations::show_delete_confirmation_modal(ui, confirm_delete_id) {
-        delete_request = Some(idx);
+    let modifiers = ui.input(|i| i.modifiers);
+    if background.clicked() && !modifiers.command && !modifiers.shift {
+        view_state.selection.clear();
+    }
+    selection::handle_rubber_band(ui, &background, &mut view_state.selection, &item_rects);
+    background.context_menu(|ui| {
+        if let Some(action) = operations::show_background_context_menu(ui, node, clipboard) {
+            actions.push(action);
+        }
+        selection::show_selection_menu(ui, &mut view_state.selection, &order);
+        ui.separator();
+        if let Some(request) = common::sort_menu(ui, settings) {
+            sort_request = Some(request);
+        }
+    });
+
+    // The inactive pane leaves the keyboard to the active one
+    if !view_state.inactive {
+        let selected = common::selected_nodes(&visible, &view_state.selection);
+        let selected_paths: Vec<PathBuf> =
+            selected.iter().map(|child| child.path.clone()).collect();
+        if let Some(action) =
+            operations::handle_clipboard_shortcuts(ui, &selected_paths, &node.path)
+        {
+            actions.push(action);
+        }
+        if let Some(action) = common::handle_rename_shortcut(ui, view_state) {
+            actions.push(action);
+        }
+        if let Some(action) = operations::handle_delete_shortcuts(ui, &selected, confirm_delete_id)
+        {
+            actions.push(action);
+        }
+        selection::handle_selection_shortcuts(ui, &mut view_state.selection, &order);
+        if let Some(path) =
+            keyboard::handle_navigation_keys(ui, view_state, node, &visible, 1, page_size)
+        {
+            nav_request = Some(path);
+        }
     }
 
-    // Execute operations
-    if let Some(idx) = delete_request {
-        operations::execute_delete(node, idx);
+    // Handle delete confirmation modal
+    if let Some(paths) = operations::show_delete_confirmation_modal(ui, confirm_delete_id) {
+        actions.push(FileAction::Delete(paths));
     }
 
+    // Dragging a selected item moves the whole selection
     if let Some((from_idx, target_folder_idx)) = move_request {
-        operations::execute_move(node, from_idx, target_folder_idx, original_children);
+        let to_dir = &children[target_folder_idx].path;
+        let paths = common::action_targets(&visible, &view_state.selection, &children[from_idx])
+            .into_iter()
+            .map(|child| child.path.clone())
+            .filter(|path| path != to_dir)
+            .collect();
+        actions.push(FileAction::Move {
+            paths,
+            to_dir: to_dir.clone(),
+        });
     }
 
-    nav_request
+    // Items dragged over from the other pane move into the folder they were dropped on, or
+    // into the shown folder
+    if let Some((paths, folder_idx)) = pane_drop {
+        let to_dir = folder_idx.map_or(&node.path, |idx| &children[idx].path);
+        let paths = paths.into_iter().filter(|path| path != to_dir).collect();
+        actions.push(FileAction::Move {
+            paths,
+            to_dir: to_dir.clone(),
+        });
+    }
+
+    ViewResponse {
+        nav_request,
+        new_tab_request,
+        sort_request,
+        columns_request,
+        actions,
+    }
 }
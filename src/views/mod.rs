@@ -1,18 +1,83 @@
+pub mod columns;
 pub mod common;
 pub mod grid;
+pub mod keyboard;
 pub mod list;
+pub mod selection;
+pub mod trash;
 
-use crate::file_system::file_tree::FileNode;
-use crate::ui::settings::{Settings, View};
+use crate::file_system::{
+    file_tree::FileNode,
+    operations::{Clipboard, FileAction},
+};
+use crate::ui::settings::{Settings, SortKey, View};
+use columns::ListColumn;
 use eframe::egui;
+use selection::Selection;
+use std::path::{Path, PathBuf};
+
+// Per view interaction state that outlives a frame
+#[derive(Default)]
+pub struct ViewState {
+    pub selection: Selection,
+    pub rename: Option<RenameState>,
+    // Set on the pane that isn't active in dual-pane mode, it ignores the keyboard
+    pub inactive: bool,
+}
+
+pub struct RenameState {
+    pub path: PathBuf,
+    pub text: String,
+    // Characters to pre-select when the field opens, the stem for files
+    pub select_len: Option<usize>,
+    // Why the text can't be the new name, checked again whenever it's edited
+    pub error: Option<String>,
+}
+
+impl ViewState {
+    pub fn start_rename(&mut self, path: &Path) {
+        let text = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let select_len = match text.rfind('.') {
+            Some(idx) if idx > 0 && !path.is_dir() => text[..idx].chars().count(),
+            _ => text.chars().count(),
+        };
+
+        self.selection.select_only(path);
+        self.rename = Some(RenameState {
+            path: path.to_path_buf(),
+            text,
+            select_len: Some(select_len),
+            error: None,
+        });
+    }
+}
+
+// What the user asked for while the view was drawn
+#[derive(Default)]
+pub struct ViewResponse {
+    pub nav_request: Option<PathBuf>,
+    // A folder to open in a new tab, left in the background
+    pub new_tab_request: Option<PathBuf>,
+    // A sort key and whether it's descending, from a header or the sort menu
+    pub sort_request: Option<(SortKey, bool)>,
+    // The list columns after they were resized, moved, shown or hidden
+    pub columns_request: Option<Vec<ListColumn>>,
+    pub actions: Vec<FileAction>,
+}
 
 pub fn render_file_node(
     ui: &mut egui::Ui,
-    node: &mut FileNode,
+    node: &FileNode,
+    state: &mut ViewState,
     settings: &Settings,
-) -> Option<std::path::PathBuf> {
+    clipboard: Option<&Clipboard>,
+) -> ViewResponse {
     match settings.view {
-        View::Grid => grid::render_grid_view(ui, node, settings),
-        View::List => list::render_list_view(ui, node, settings),
+        View::Grid => grid::render_grid_view(ui, node, state, settings, clipboard),
+        View::List => list::render_list_view(ui, node, state, settings, clipboard),
     }
 }
@@ -0,0 +1,219 @@
+use eframe::egui;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+// The selected items of a view. Shift ranges start at the anchor, the last plainly or
+// Ctrl-clicked item. The focus is the item the keyboard moves from, it doesn't have to be
+// selected. Orders passed in are the items as shown, after sorting and filtering.
+#[derive(Default)]
+pub struct Selection {
+    paths: HashSet<PathBuf>,
+    anchor: Option<PathBuf>,
+    focus: Option<PathBuf>,
+    // Set when the keyboard moved the focus, so the view scrolls to it
+    scroll_to_focus: bool,
+    // What was selected when a rubber band drag started, Ctrl-drags add to it
+    band_base: Option<HashSet<PathBuf>>,
+}
+
+impl Selection {
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    // The selected item when exactly one is selected
+    pub fn single(&self) -> Option<&Path> {
+        match self.paths.len() {
+            1 => self.paths.iter().next().map(PathBuf::as_path),
+            _ => None,
+        }
+    }
+
+    pub fn focus(&self) -> Option<&Path> {
+        self.focus.as_deref()
+    }
+
+    // Whether the view should scroll to the focused item, only true once per keyboard move
+    pub fn take_scroll_to_focus(&mut self) -> bool {
+        std::mem::take(&mut self.scroll_to_focus)
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.anchor = None;
+    }
+
+    pub fn select_only(&mut self, path: &Path) {
+        self.paths.clear();
+        self.paths.insert(path.to_path_buf());
+        self.anchor = Some(path.to_path_buf());
+        self.focus = Some(path.to_path_buf());
+    }
+
+    pub fn toggle(&mut self, path: &Path) {
+        if !self.paths.remove(path) {
+            self.paths.insert(path.to_path_buf());
+        }
+        self.anchor = Some(path.to_path_buf());
+        self.focus = Some(path.to_path_buf());
+    }
+
+    // Select everything between the anchor and path, keeping the rest when additive
+    pub fn select_range(&mut self, order: &[&Path], path: &Path, additive: bool) {
+        let position = |target: &Path| order.iter().position(|p| *p == target);
+        let Some(end) = position(path) else {
+            return;
+        };
+        let start = self.anchor.as_deref().and_then(position).unwrap_or(end);
+
+        if !additive {
+            self.paths.clear();
+        }
+        let (from, to) = (start.min(end), start.max(end));
+        self.paths
+            .extend(order[from..=to].iter().map(|p| p.to_path_buf()));
+        self.focus = Some(path.to_path_buf());
+    }
+
+    pub fn select_all(&mut self, order: &[&Path]) {
+        self.paths = order.iter().map(|p| p.to_path_buf()).collect();
+    }
+
+    pub fn invert(&mut self, order: &[&Path]) {
+        self.paths = order
+            .iter()
+            .filter(|p| !self.paths.contains(**p))
+            .map(|p| p.to_path_buf())
+            .collect();
+    }
+
+    // Follow a selected item to its new name
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        if self.paths.remove(from) {
+            self.paths.insert(to.to_path_buf());
+        }
+        if self.anchor.as_deref() == Some(from) {
+            self.anchor = Some(to.to_path_buf());
+        }
+        if self.focus.as_deref() == Some(from) {
+            self.focus = Some(to.to_path_buf());
+        }
+    }
+
+    // Forget items that were deleted, moved away or hidden
+    pub fn retain_visible(&mut self, order: &[&Path]) {
+        if self.paths.is_empty() && self.focus.is_none() {
+            return;
+        }
+        let visible: HashSet<&Path> = order.iter().copied().collect();
+        self.paths.retain(|p| visible.contains(p.as_path()));
+        if self
+            .focus
+            .as_deref()
+            .is_some_and(|focus| !visible.contains(focus))
+        {
+            self.focus = None;
+        }
+    }
+
+    // Keyboard movement: plain selects only the new item, Shift extends a range to it and
+    // Ctrl moves the focus alone
+    pub fn move_focus(&mut self, path: &Path, modifiers: egui::Modifiers, order: &[&Path]) {
+        if modifiers.shift {
+            self.select_range(order, path, modifiers.command);
+        } else if modifiers.command {
+            self.focus = Some(path.to_path_buf());
+        } else {
+            self.select_only(path);
+        }
+        self.scroll_to_focus = true;
+    }
+
+    // Plain click selects only the item, Ctrl toggles it and Shift extends a range
+    pub fn click(&mut self, path: &Path, modifiers: egui::Modifiers, order: &[&Path]) {
+        if modifiers.shift {
+            self.select_range(order, path, modifiers.command);
+        } else if modifiers.command {
+            self.toggle(path);
+        } else {
+            self.select_only(path);
+        }
+    }
+}
+
+// Ctrl+A selects everything, Ctrl+I inverts the selection
+pub fn handle_selection_shortcuts(ui: &egui::Ui, selection: &mut Selection, order: &[&Path]) {
+    if ui.ctx().wants_keyboard_input() {
+        return;
+    }
+
+    let select_all = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::A);
+    let invert = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::I);
+
+    if ui.input_mut(|i| i.consume_shortcut(&select_all)) {
+        selection.select_all(order);
+    } else if ui.input_mut(|i| i.consume_shortcut(&invert)) {
+        selection.invert(order);
+    }
+}
+
+// Entries added to the context menu of a view's empty space
+pub fn show_selection_menu(ui: &mut egui::Ui, selection: &mut Selection, order: &[&Path]) {
+    ui.separator();
+
+    if ui.button("Select All").clicked() {
+        selection.select_all(order);
+        ui.close();
+    }
+    if ui.button("Invert Selection").clicked() {
+        selection.invert(order);
+        ui.close();
+    }
+}
+
+// Dragging on empty space selects every item the band touches
+pub fn handle_rubber_band(
+    ui: &egui::Ui,
+    background: &egui::Response,
+    selection: &mut Selection,
+    item_rects: &[(&Path, egui::Rect)],
+) {
+    if background.drag_started() {
+        let keep_selection = ui.input(|i| i.modifiers.command);
+        selection.band_base = Some(if keep_selection {
+            selection.paths.clone()
+        } else {
+            HashSet::new()
+        });
+    }
+
+    if background.dragged()
+        && let Some(base) = &selection.band_base
+        && let (Some(origin), Some(pointer)) =
+            ui.input(|i| (i.pointer.press_origin(), i.pointer.interact_pos()))
+    {
+        let band = egui::Rect::from_two_pos(origin, pointer);
+
+        let mut paths = base.clone();
+        paths.extend(
+            item_rects
+                .iter()
+                .filter(|(_, rect)| rect.intersects(band))
+                .map(|(path, _)| path.to_path_buf()),
+        );
+        selection.paths = paths;
+
+        let color = ui.visuals().selection.bg_fill;
+        ui.painter().rect(
+            band,
+            2.0,
+            color.gamma_multiply(0.2),
+            egui::Stroke::new(1.0, color),
+            egui::StrokeKind::Inside,
+        );
+    }
+
+    if background.drag_stopped() {
+        selection.band_base = None;
+    }
+}
@@ -0,0 +1,122 @@
+use crate::file_system::{formatting, operations::FileAction, trash::TrashEntry};
+use eframe::egui;
+
+pub fn render_trash_view(ui: &mut egui::Ui, entries: &[TrashEntry]) -> Vec<FileAction> {
+    let mut actions = Vec::new();
+
+    // Some(entry) deletes one item, None empties the whole trash
+    let confirm_id = ui.id().with("confirm_trash_delete");
+
+    ui.horizontal(|ui| {
+        ui.heading("Trash");
+        ui.label(egui::RichText::new(format!("{} items", entries.len())).weak());
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui
+                .add_enabled(!entries.is_empty(), egui::Button::new("Empty Trash"))
+                .clicked()
+            {
+                ui.data_mut(|d| d.insert_temp::<Option<TrashEntry>>(confirm_id, None));
+            }
+        });
+    });
+    ui.separator();
+
+    if entries.is_empty() {
+        ui.label(egui::RichText::new("The trash is empty.").weak());
+    }
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            egui::Grid::new("trash_entries")
+                .num_columns(4)
+                .striped(true)
+                .spacing([24.0, 8.0])
+                .show(ui, |ui| {
+                    for entry in entries {
+                        let name = entry
+                            .original_path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| entry.name.clone());
+                        let location = entry
+                            .original_path
+                            .parent()
+                            .map(|parent| parent.display().to_string())
+                            .unwrap_or_default();
+
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new(formatting::get_file_icon(&name, entry.is_dir))
+                                    .size(20.0),
+                            );
+                            ui.label(egui::RichText::new(name).size(16.0));
+                        });
+                        ui.label(egui::RichText::new(location).monospace().weak());
+                        ui.label(
+                            entry
+                                .deletion_date
+                                .as_deref()
+                                .map(|date| date.replace('T', " "))
+                                .unwrap_or_else(|| formatting::UNAVAILABLE.to_string()),
+                        );
+
+                        ui.horizontal(|ui| {
+                            let restore = ui
+                                .button(egui_phosphor::regular::ARROW_COUNTER_CLOCKWISE)
+                                .on_hover_text(format!(
+                                    "Restore to {}",
+                                    entry.original_path.display()
+                                ));
+                            if restore.clicked() {
+                                actions.push(FileAction::RestoreFromTrash(entry.clone()));
+                            }
+
+                            let delete = ui
+                                .button(egui_phosphor::regular::TRASH)
+                                .on_hover_text("Delete permanently");
+                            if delete.clicked() {
+                                ui.data_mut(|d| d.insert_temp(confirm_id, Some(entry.clone())));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+
+    if let Some(target) = ui.data(|d| d.get_temp::<Option<TrashEntry>>(confirm_id)) {
+        egui::Window::new("Confirm Delete")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ui.ctx(), |ui| {
+                match &target {
+                    Some(entry) => ui.label(format!(
+                        "Permanently delete '{}'?",
+                        entry.original_path.display()
+                    )),
+                    None => ui.label(format!(
+                        "Permanently delete all {} items in the trash?",
+                        entries.len()
+                    )),
+                };
+                ui.label("This can't be undone.");
+
+                ui.horizontal(|ui| {
+                    if ui.button("Cancel").clicked() {
+                        ui.data_mut(|d| d.remove::<Option<TrashEntry>>(confirm_id));
+                    }
+                    if ui.button("Delete").clicked() {
+                        actions.push(match target.clone() {
+                            Some(entry) => FileAction::DeleteFromTrash(entry),
+                            None => FileAction::EmptyTrash,
+                        });
+                        ui.data_mut(|d| d.remove::<Option<TrashEntry>>(confirm_id));
+                    }
+                });
+            });
+    }
+
+    actions
+}